@@ -0,0 +1,432 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use piston_window::{Button, Key, MouseButton};
+
+use rather3d::error::{LoadError, LoadErrorKind};
+
+/// Something the user can ask the viewer to do
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    StrafeLeft,
+    StrafeRight,
+    MoveUp,
+    MoveDown,
+    TurnLeft,
+    TurnRight,
+    RollLeft,
+    RollRight,
+    Fast,
+    Slow,
+    NextController,
+    NextCamera,
+    SaveScene,
+    Screenshot,
+    ToggleWireframe,
+    NextShading,
+    NextFilter,
+    NextLight,
+    LightLeft,
+    LightRight,
+    LightUp,
+    LightDown,
+    ToggleCulling,
+    ToggleTriangles,
+    NextGroup,
+    ToggleGroup,
+    ToggleHud,
+    /// Hide the cursor and keep it in the window, so moving the mouse looks around
+    CaptureMouse,
+    ReleaseMouse,
+    /// Close the viewer, unless the same key releases the mouse first
+    Quit,
+}
+
+/// The name of each action in bindings files
+const ACTION_NAMES: &[(Action, &str)] = &[
+    (Action::MoveForward, "move_forward"),
+    (Action::MoveBack, "move_back"),
+    (Action::StrafeLeft, "strafe_left"),
+    (Action::StrafeRight, "strafe_right"),
+    (Action::MoveUp, "move_up"),
+    (Action::MoveDown, "move_down"),
+    (Action::TurnLeft, "turn_left"),
+    (Action::TurnRight, "turn_right"),
+    (Action::RollLeft, "roll_left"),
+    (Action::RollRight, "roll_right"),
+    (Action::Fast, "fast"),
+    (Action::Slow, "slow"),
+    (Action::NextController, "next_controller"),
+    (Action::NextCamera, "next_camera"),
+    (Action::SaveScene, "save_scene"),
+    (Action::Screenshot, "screenshot"),
+    (Action::ToggleWireframe, "toggle_wireframe"),
+    (Action::NextShading, "next_shading"),
+    (Action::NextFilter, "next_filter"),
+    (Action::NextLight, "next_light"),
+    (Action::LightLeft, "light_left"),
+    (Action::LightRight, "light_right"),
+    (Action::LightUp, "light_up"),
+    (Action::LightDown, "light_down"),
+    (Action::ToggleCulling, "toggle_culling"),
+    (Action::ToggleTriangles, "toggle_triangles"),
+    (Action::NextGroup, "next_group"),
+    (Action::ToggleGroup, "toggle_group"),
+    (Action::ToggleHud, "toggle_hud"),
+    (Action::CaptureMouse, "capture_mouse"),
+    (Action::ReleaseMouse, "release_mouse"),
+    (Action::Quit, "quit"),
+];
+
+/// The bindings used for any action the bindings file doesn't mention, in the same format
+pub const DEFAULT_BINDINGS: &str = "\
+move_forward Up W
+move_back Down S
+strafe_left A
+strafe_right D
+move_up Space
+move_down Z
+turn_left Left
+turn_right Right
+roll_left Q
+roll_right E
+fast LShift RShift
+slow LAlt RAlt
+next_controller C
+next_camera V
+save_scene F2
+screenshot F12
+toggle_wireframe F
+next_shading M
+next_filter G
+next_light N
+light_left J
+light_right L
+light_up I
+light_down K
+toggle_culling B
+toggle_triangles T
+next_group Tab
+toggle_group H
+toggle_hud F1
+capture_mouse MouseLeft
+release_mouse Escape
+quit Escape
+";
+
+/// Modifier keys that have to be held down for a binding to apply, where either the left or the
+/// right key will do
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Modifiers {
+    fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt].iter().filter(|held| **held).count()
+    }
+
+    /// Whether every modifier needed is being held down, along with any others
+    fn are_held(&self, held: &HashSet<Button>) -> bool {
+        let pressed = |keys: [Key; 2]| keys.iter().any(|key| held.contains(&Button::Keyboard(*key)));
+        (!self.ctrl || pressed([Key::LCtrl, Key::RCtrl]))
+            && (!self.shift || pressed([Key::LShift, Key::RShift]))
+            && (!self.alt || pressed([Key::LAlt, Key::RAlt]))
+    }
+}
+
+/// A key or mouse button, along with the modifiers that have to be held with it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub modifiers: Modifiers,
+    pub button: Button,
+}
+
+impl Binding {
+    /// Parse a binding such as `W`, `Ctrl+S` or `Shift+MouseLeft`, ignoring case
+    pub fn parse(text: &str, buttons: &HashMap<String, Button>) -> Result<Self, LoadErrorKind> {
+        let mut modifiers = Modifiers::default();
+        let mut parts: Vec<&str> = text.split('+').collect();
+        let name = parts.pop().unwrap_or_default();
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" => modifiers.ctrl = true,
+                "shift" => modifiers.shift = true,
+                "alt" => modifiers.alt = true,
+                _ => {
+                    return Err(LoadErrorKind::UnknownName {
+                        element: "modifier",
+                        name: part.to_string(),
+                    })
+                },
+            }
+        }
+
+        let button = buttons.get(&name.to_lowercase()).ok_or_else(|| LoadErrorKind::UnknownName {
+            element: "key",
+            name: name.to_string(),
+        })?;
+        Ok(Self {
+            modifiers,
+            button: *button,
+        })
+    }
+}
+
+/// Every key and mouse button that can be bound, by their lowercase names.  Keys are named as in
+/// piston, such as `A`, `D1`, `LShift` or `F12`, and mouse buttons have `Mouse` in front, such as
+/// `MouseLeft` or `MouseX1`
+fn button_names() -> HashMap<String, Button> {
+    // key codes are either characters, or special keys starting from 0x4000_0000
+    let keys = (0..0x80).chain(0x4000_0000..0x4000_0200).map(Key::from).map(Button::Keyboard);
+    let mouse_buttons = (0..9).map(MouseButton::from).map(Button::Mouse);
+
+    keys.chain(mouse_buttons)
+        .filter_map(|button| match button {
+            Button::Keyboard(Key::Unknown) | Button::Mouse(MouseButton::Unknown) => None,
+            Button::Keyboard(key) => Some((format!("{:?}", key).to_lowercase(), button)),
+            Button::Mouse(mouse_button) => Some((format!("mouse{:?}", mouse_button).to_lowercase(), button)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Which keys and mouse buttons do what
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    bindings: Vec<(Action, Binding)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        let mut bindings = Self {
+            bindings: vec![],
+        };
+        let buttons = button_names();
+        for line in DEFAULT_BINDINGS.lines() {
+            bindings
+                .read_line(line, &buttons)
+                .expect("the default bindings should be valid");
+        }
+        bindings
+    }
+}
+
+impl Bindings {
+    /// Read a bindings file, where each line is an action followed by the keys and mouse buttons
+    /// that do it.  Each action in the file replaces all its default bindings, so an action without
+    /// any is turned off, and `#` starts a comment
+    pub fn read(filename: &Path) -> Result<Self, LoadError> {
+        let file = File::open(filename).map_err(|err| LoadError::new(filename, None, err.into()))?;
+        let reader = BufReader::new(file);
+
+        let mut bindings = Self::default();
+        let buttons = button_names();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| LoadError::new(filename, Some(i + 1), err.into()))?;
+            bindings
+                .read_line(&line, &buttons)
+                .map_err(|kind| LoadError::new(filename, Some(i + 1), kind))?;
+        }
+        Ok(bindings)
+    }
+
+    /// Read the bindings file given, or the one in the user's config directory if there is one,
+    /// otherwise use the defaults
+    pub fn load(filename: Option<&str>) -> Result<Self, LoadError> {
+        match filename {
+            Some(filename) => Self::read(Path::new(filename)),
+            None => match config_file() {
+                Some(path) => match Self::read(&path) {
+                    Err(LoadError {
+                        kind: LoadErrorKind::Io(err),
+                        ..
+                    }) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+                    result => result,
+                },
+                None => Ok(Self::default()),
+            },
+        }
+    }
+
+    fn read_line(&mut self, line: &str, buttons: &HashMap<String, Button>) -> Result<(), LoadErrorKind> {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+
+        let action = ACTION_NAMES
+            .iter()
+            .find(|(_, action_name)| *action_name == name)
+            .map(|(action, _)| *action)
+            .ok_or_else(|| LoadErrorKind::UnknownName {
+                element: "action",
+                name: name.to_string(),
+            })?;
+
+        self.bindings.retain(|(bound, _)| *bound != action);
+        for word in words {
+            self.bindings.push((action, Binding::parse(word, buttons)?));
+        }
+        Ok(())
+    }
+
+    /// The actions to do when the button is pressed while the others are held down.  When the
+    /// button is bound more than once, only the bindings needing the most modifiers apply, so that
+    /// `Ctrl+S` doesn't also do what `S` does
+    pub fn pressed(&self, button: Button, held: &HashSet<Button>) -> Vec<Action> {
+        let matching: Vec<&(Action, Binding)> = self
+            .bindings
+            .iter()
+            .filter(|(_, binding)| binding.button == button && binding.modifiers.are_held(held))
+            .collect();
+        let most_modifiers = matching
+            .iter()
+            .map(|(_, binding)| binding.modifiers.count())
+            .max()
+            .unwrap_or(0);
+        matching
+            .into_iter()
+            .filter(|(_, binding)| binding.modifiers.count() == most_modifiers)
+            .map(|(action, _)| *action)
+            .collect()
+    }
+
+    /// Whether the buttons being held down are doing the action.  Like `pressed`, a binding doesn't
+    /// count while another binding for the same button needing more modifiers is held, so holding
+    /// `Ctrl+S` doesn't also do what `S` does
+    pub fn is_held(&self, action: Action, held: &HashSet<Button>) -> bool {
+        let is_active = |binding: &Binding| held.contains(&binding.button) && binding.modifiers.are_held(held);
+        self.bindings.iter().any(|(bound, binding)| {
+            *bound == action
+                && is_active(binding)
+                && !self.bindings.iter().any(|(_, other)| {
+                    other.button == binding.button && other.modifiers.count() > binding.modifiers.count() && is_active(other)
+                })
+        })
+    }
+}
+
+/// Where the user's own bindings are kept, following the XDG base directory spec on Unix
+fn config_file() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(dir.join("rather3d").join("bindings"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: Key) -> Button {
+        Button::Keyboard(key)
+    }
+
+    fn bindings(lines: &str) -> Result<Bindings, LoadErrorKind> {
+        let mut bindings = Bindings::default();
+        let buttons = button_names();
+        for line in lines.lines() {
+            bindings.read_line(line, &buttons)?;
+        }
+        Ok(bindings)
+    }
+
+    #[test]
+    fn parse_bindings() {
+        let buttons = button_names();
+        let binding = Binding::parse("ctrl+Shift+s", &buttons).unwrap();
+        assert_eq!(binding.button, key(Key::S));
+        assert_eq!(
+            binding.modifiers,
+            Modifiers {
+                ctrl: true,
+                shift: true,
+                alt: false
+            }
+        );
+        assert_eq!(
+            Binding::parse("MouseRight", &buttons).unwrap().button,
+            Button::Mouse(MouseButton::Right)
+        );
+        assert_eq!(Binding::parse("F12", &buttons).unwrap().button, key(Key::F12));
+    }
+
+    #[test]
+    fn unknown_names_are_errors() {
+        let buttons = button_names();
+        assert!(matches!(
+            Binding::parse("Super+S", &buttons),
+            Err(LoadErrorKind::UnknownName {
+                element: "modifier",
+                ..
+            })
+        ));
+        assert!(matches!(
+            Binding::parse("Ctrl+NoSuchKey", &buttons),
+            Err(LoadErrorKind::UnknownName {
+                element: "key",
+                ..
+            })
+        ));
+        assert!(matches!(
+            bindings("fly_away W"),
+            Err(LoadErrorKind::UnknownName {
+                element: "action",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn file_replaces_the_default_bindings() {
+        let bindings = bindings("# comment\nmove_forward I  # trailing comment\nroll_left\n").unwrap();
+        let held = HashSet::new();
+        assert_eq!(
+            bindings.pressed(key(Key::I), &held),
+            vec![Action::LightUp, Action::MoveForward]
+        );
+        assert!(!bindings.pressed(key(Key::W), &held).contains(&Action::MoveForward));
+        assert!(bindings.pressed(key(Key::Q), &held).is_empty());
+        assert_eq!(bindings.pressed(key(Key::S), &held), vec![Action::MoveBack]);
+    }
+
+    #[test]
+    fn bindings_with_more_modifiers_win() {
+        let bindings = bindings("save_scene Ctrl+S").unwrap();
+        let held: HashSet<Button> = [key(Key::LCtrl), key(Key::S)].into_iter().collect();
+        assert_eq!(bindings.pressed(key(Key::S), &held), vec![Action::SaveScene]);
+        assert!(bindings.is_held(Action::SaveScene, &held));
+
+        // either the left or the right modifier will do
+        let held: HashSet<Button> = [key(Key::RCtrl), key(Key::S)].into_iter().collect();
+        assert_eq!(bindings.pressed(key(Key::S), &held), vec![Action::SaveScene]);
+    }
+
+    #[test]
+    fn held_bindings_with_more_modifiers_win() {
+        let bindings = bindings("save_scene Ctrl+S F2").unwrap();
+        let held: HashSet<Button> = [key(Key::LCtrl), key(Key::S)].into_iter().collect();
+        assert!(bindings.is_held(Action::SaveScene, &held));
+        assert!(!bindings.is_held(Action::MoveBack, &held));
+        assert!(!bindings.is_held(Action::MoveDown, &held));
+
+        let held: HashSet<Button> = [key(Key::S)].into_iter().collect();
+        assert!(bindings.is_held(Action::MoveBack, &held));
+        assert!(!bindings.is_held(Action::SaveScene, &held));
+
+        // modifiers that no binding for the key needs don't stop it
+        let held: HashSet<Button> = [key(Key::LAlt), key(Key::S)].into_iter().collect();
+        assert!(bindings.is_held(Action::MoveBack, &held));
+    }
+}
@@ -0,0 +1,78 @@
+use std::f64::consts::PI;
+
+use nalgebra::{Matrix4, Point3, UnitQuaternion, Vector3};
+
+use crate::math;
+
+/// The parameters of the perspective projection, with the vertical field of view in degrees
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Projection {
+    pub fov: f64,
+    pub near: f64,
+    pub far: f64,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self {
+            fov: 45.0,
+            near: 1.0,
+            far: 10000.0,
+        }
+    }
+}
+
+impl Projection {
+    pub fn matrix(&self, aspect: f64) -> Matrix4<f64> {
+        math::perspective_transform_fov(self.fov * PI / 180.0, aspect, self.near, self.far)
+    }
+}
+
+/// Where the camera is and which way it's looking, along with how it projects the world onto the screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub position: Point3<f64>,
+    /// The rotation from the camera's space, where it looks down the +z axis, into world space
+    pub orientation: UnitQuaternion<f64>,
+    pub projection: Projection,
+}
+
+impl Camera {
+    /// A camera at the origin looking down the +z axis
+    pub fn new(projection: Projection) -> Self {
+        Self {
+            position: Point3::origin(),
+            orientation: UnitQuaternion::identity(),
+            projection,
+        }
+    }
+
+    /// The transform from world space into the space of the camera
+    pub fn view_matrix(&self) -> Matrix4<f64> {
+        self.orientation.inverse().to_homogeneous() * math::translate(-1.0 * self.position)
+    }
+
+    /// The orientation as the pitch, yaw and roll in degrees
+    pub fn euler_angles(&self) -> Vector3<f64> {
+        math::euler_from_quaternion(&self.orientation)
+    }
+
+    pub fn set_euler_angles(&mut self, angles: Vector3<f64>) {
+        self.orientation = math::quaternion_from_euler(angles);
+    }
+
+    /// The direction the camera is looking in, in world space
+    pub fn forward(&self) -> Vector3<f64> {
+        self.orientation * Vector3::z()
+    }
+
+    /// The direction to the left of the screen, in world space
+    pub fn left(&self) -> Vector3<f64> {
+        self.orientation * Vector3::x()
+    }
+
+    /// The direction to the top of the screen, in world space
+    pub fn up(&self) -> Vector3<f64> {
+        self.orientation * Vector3::y()
+    }
+}
@@ -0,0 +1,180 @@
+use nalgebra::{Matrix3, Vector4};
+
+/// Values that are carried along with a vertex and interpolated when an edge is clipped
+pub trait Interpolate: Copy {
+    fn interpolate(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Interpolate for () {
+    fn interpolate(&self, _other: &Self, _t: f64) -> Self {}
+}
+
+/// The signed distances of a clip space point from each of the six frustum planes, which are all
+/// positive or zero when the point is inside.  The planes are -w <= x <= w, -w <= y <= w, and -w <= z <= w
+fn plane_distances(point: &Vector4<f64>) -> [f64; 6] {
+    let (x, y, z, w) = (point.x, point.y, point.z, point.w);
+    [w + x, w - x, w + y, w - y, w + z, w - z]
+}
+
+pub fn is_inside(point: &Vector4<f64>) -> bool {
+    plane_distances(point).iter().all(|distance| *distance >= 0.0)
+}
+
+/// Clip a line in clip space (before the perspective divide) against the view frustum, returning
+/// the part of the line that's inside, if any
+pub fn clip_line(from: Vector4<f64>, to: Vector4<f64>) -> Option<(Vector4<f64>, Vector4<f64>)> {
+    let from_distances = plane_distances(&from);
+    let to_distances = plane_distances(&to);
+
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for (d0, d1) in from_distances.into_iter().zip(to_distances) {
+        if d0 < 0.0 && d1 < 0.0 {
+            return None;
+        } else if d0 < 0.0 {
+            t0 = t0.max(d0 / (d0 - d1));
+        } else if d1 < 0.0 {
+            t1 = t1.min(d0 / (d0 - d1));
+        }
+    }
+
+    if t0 > t1 {
+        return None;
+    }
+    Some((from.lerp(&to, t0), from.lerp(&to, t1)))
+}
+
+/// Clip a polygon in clip space against each of the frustum planes in turn (Sutherland-Hodgman),
+/// interpolating each vertex's attributes where new vertices are created.  The result has no
+/// vertices if the polygon is entirely outside the frustum
+pub fn clip_polygon<T: Interpolate>(vertices: &[(Vector4<f64>, T)]) -> Vec<(Vector4<f64>, T)> {
+    if vertices.iter().all(|(point, _)| is_inside(point)) {
+        return vertices.to_vec();
+    }
+
+    let mut output = vertices.to_vec();
+    for plane in 0..6 {
+        let input = std::mem::take(&mut output);
+        for (i, current) in input.iter().enumerate() {
+            let next = &input[(i + 1) % input.len()];
+            let d0 = plane_distances(&current.0)[plane];
+            let d1 = plane_distances(&next.0)[plane];
+
+            if d0 >= 0.0 {
+                output.push(*current);
+            }
+            if (d0 >= 0.0) != (d1 >= 0.0) {
+                let t = d0 / (d0 - d1);
+                output.push((current.0.lerp(&next.0, t), current.1.interpolate(&next.1, t)));
+            }
+        }
+
+        if output.is_empty() {
+            break;
+        }
+    }
+    output
+}
+
+/// Whether a polygon in clip space is facing towards the camera, which is when its vertices are
+/// counter-clockwise on screen.  This uses the determinant of the x, y and w coordinates, which gives
+/// the same result as checking the winding after the perspective divide, but also works for vertices
+/// behind the camera.  Polygons are treated as a fan of triangles, and edge-on polygons aren't front facing
+pub fn is_front_facing(points: &[Vector4<f64>]) -> bool {
+    let mut total = 0.0;
+    for i in 1..points.len().saturating_sub(1) {
+        let (a, b, c) = (points[0], points[i], points[i + 1]);
+        total += Matrix3::new(a.x, b.x, c.x, a.y, b.y, c.y, a.w, b.w, c.w).determinant();
+    }
+    total > 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether the point is inside the frustum, allowing for rounding where it was clipped
+    fn is_nearly_inside(point: &Vector4<f64>) -> bool {
+        plane_distances(point).iter().all(|distance| *distance >= -1e-9)
+    }
+
+    #[test]
+    fn line_inside_is_unchanged() {
+        let (from, to) = (Vector4::new(-0.5, 0.0, 0.5, 1.0), Vector4::new(0.5, 0.5, 0.0, 1.0));
+        assert_eq!(clip_line(from, to), Some((from, to)));
+    }
+
+    #[test]
+    fn line_behind_the_camera_is_removed() {
+        let (from, to) = (Vector4::new(0.0, 0.0, -3.0, -2.0), Vector4::new(1.0, 0.0, -5.0, -4.0));
+        assert_eq!(clip_line(from, to), None);
+    }
+
+    #[test]
+    fn line_going_behind_the_camera_stops_at_the_near_plane() {
+        let (from, to) = (Vector4::new(0.0, 0.0, 0.5, 1.0), Vector4::new(0.0, 0.0, -3.0, -2.0));
+        let (clipped_from, clipped_to) = clip_line(from, to).unwrap();
+        assert_eq!(clipped_from, from);
+        assert!(is_nearly_inside(&clipped_to));
+        assert!(clipped_to.w > 0.0);
+        assert!((clipped_to.z + clipped_to.w).abs() < 1e-9);
+    }
+
+    #[test]
+    fn line_passing_outside_the_frustum_is_removed() {
+        // both ends are outside different planes, and the line between them misses the frustum
+        let (from, to) = (Vector4::new(2.0, 0.0, 0.5, 1.0), Vector4::new(0.0, 0.0, -3.0, -2.0));
+        assert_eq!(clip_line(from, to), None);
+    }
+
+    #[test]
+    fn polygon_partly_behind_the_camera() {
+        let triangle = [
+            (Vector4::new(-0.5, -0.5, 0.5, 1.0), ()),
+            (Vector4::new(0.5, -0.5, 0.5, 1.0), ()),
+            (Vector4::new(0.0, 0.5, -3.0, -2.0), ()),
+        ];
+        let clipped = clip_polygon(&triangle);
+        // the vertices in front are kept, and the edges to the one behind are cut by the side
+        // planes as well as the near plane
+        assert_eq!(clipped[0].0, triangle[0].0);
+        assert_eq!(clipped[1].0, triangle[1].0);
+        assert!(clipped.len() >= 4);
+        for (point, _) in &clipped {
+            assert!(is_nearly_inside(point), "{:?} is outside the frustum", point);
+            assert!(point.w > 0.0);
+        }
+    }
+
+    #[test]
+    fn polygon_behind_the_camera_is_removed() {
+        let triangle = [
+            (Vector4::new(-0.5, -0.5, -2.0, -1.0), ()),
+            (Vector4::new(0.5, -0.5, -2.0, -1.0), ()),
+            (Vector4::new(0.0, 0.5, -3.0, -2.0), ()),
+        ];
+        assert!(clip_polygon(&triangle).is_empty());
+    }
+
+    #[test]
+    fn clipped_vertices_are_interpolated() {
+        #[derive(Copy, Clone, Debug)]
+        struct Value(f64);
+        impl Interpolate for Value {
+            fn interpolate(&self, other: &Self, t: f64) -> Self {
+                Value(self.0 + (other.0 - self.0) * t)
+            }
+        }
+
+        // the x = w plane cuts both edges to the outside vertex half way along
+        let triangle = [
+            (Vector4::new(0.0, -0.5, 0.5, 1.0), Value(0.0)),
+            (Vector4::new(2.0, 0.0, 0.5, 1.0), Value(1.0)),
+            (Vector4::new(0.0, 0.5, 0.5, 1.0), Value(0.0)),
+        ];
+        let clipped = clip_polygon(&triangle);
+        assert_eq!(clipped.len(), 4);
+        for (point, value) in &clipped {
+            assert!((value.0 - point.x / 2.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use nalgebra::{Point3, Vector3};
+
+use crate::camera::Camera;
+use crate::math;
+
+/// How far the camera moves in a second at full speed
+pub const MOVE_SPEED: f64 = 100.0;
+/// How many degrees the camera turns in a second at full speed
+pub const TURN_SPEED: f64 = 90.0;
+/// How far up or down the camera can look, which stops it from flipping over when looking straight
+/// up or down
+pub const MAX_PITCH: f64 = 89.0;
+
+/// How the user is asking the camera to move, with each axis from -1 to 1
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Movement {
+    /// Positive to move forwards, negative to move backwards
+    pub forward: f64,
+    /// Positive to move (strafe) right, negative to move left
+    pub right: f64,
+    /// Positive to move up, negative to move down
+    pub up: f64,
+    /// Positive to turn right, negative to turn left
+    pub turn: f64,
+    /// Positive to roll right, negative to roll left
+    pub roll: f64,
+    /// How much faster or slower than `MOVE_SPEED` to move, which doesn't affect turning
+    pub speed: f64,
+}
+
+impl Default for Movement {
+    fn default() -> Self {
+        Self {
+            forward: 0.0,
+            right: 0.0,
+            up: 0.0,
+            turn: 0.0,
+            roll: 0.0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl Movement {
+    /// How far to move along each of the forward, right and up axes in the given number of seconds
+    pub fn distance(&self, dt: f64) -> Vector3<f64> {
+        Vector3::new(self.forward, self.right, self.up) * self.speed * MOVE_SPEED * dt
+    }
+}
+
+/// Something that moves the camera in response to the user's input
+pub trait Controller {
+    fn name(&self) -> &'static str;
+
+    /// Start controlling the camera, picking up from wherever the previous controller left it
+    fn attach(&mut self, _camera: &mut Camera) {}
+
+    /// Turn the camera right and down by the given degrees, from moving the mouse
+    fn look(&mut self, camera: &mut Camera, delta: [f64; 2]);
+
+    /// Move the camera on by the given number of seconds
+    fn update(&mut self, camera: &mut Camera, movement: &Movement, dt: f64);
+}
+
+/// Walks around on the horizontal plane, wherever the camera is looking, and moves straight up and
+/// down.  This keeps track of the pitch, yaw and roll itself and adds the mouse movement to them,
+/// which is how the viewer has always turned, but stops looking up or down just short of vertical
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FirstPerson {
+    /// The pitch, yaw and roll in degrees
+    pub angles: Vector3<f64>,
+}
+
+impl Controller for FirstPerson {
+    fn name(&self) -> &'static str {
+        "first person"
+    }
+
+    fn attach(&mut self, camera: &mut Camera) {
+        self.angles = camera.euler_angles();
+    }
+
+    fn look(&mut self, camera: &mut Camera, delta: [f64; 2]) {
+        self.angles.y += delta[0];
+        self.angles.x = (self.angles.x + delta[1]).clamp(-MAX_PITCH, MAX_PITCH);
+        camera.set_euler_angles(self.angles);
+    }
+
+    fn update(&mut self, camera: &mut Camera, movement: &Movement, dt: f64) {
+        self.angles.y += movement.turn * TURN_SPEED * dt;
+        self.angles.z += movement.roll * TURN_SPEED * dt;
+        let distance = movement.distance(dt);
+        let (sin, cos) = self.angles.y.to_radians().sin_cos();
+        camera.position.x -= distance.x * sin + distance.y * cos;
+        camera.position.z += distance.x * cos - distance.y * sin;
+        camera.position.y += distance.z;
+        camera.set_euler_angles(self.angles);
+    }
+}
+
+/// Flies in whichever direction the camera is looking, including up and down.  Turning is always
+/// relative to the camera's own axes, so it can loop and roll freely
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FreeFly;
+
+impl FreeFly {
+    /// Turn right around the camera's up axis, down around its left axis, and roll right around its
+    /// forward axis by the given degrees
+    fn turn(camera: &mut Camera, right: f64, down: f64, roll: f64) {
+        let yaw = math::quaternion_from_axis_angle(Vector3::y(), -right);
+        let pitch = math::quaternion_from_axis_angle(Vector3::x(), down);
+        let roll = math::quaternion_from_axis_angle(Vector3::z(), roll);
+        camera.orientation = camera.orientation * yaw * pitch * roll;
+        camera.orientation.renormalize_fast();
+    }
+}
+
+impl Controller for FreeFly {
+    fn name(&self) -> &'static str {
+        "free fly"
+    }
+
+    fn look(&mut self, camera: &mut Camera, delta: [f64; 2]) {
+        Self::turn(camera, delta[0], delta[1], 0.0);
+    }
+
+    fn update(&mut self, camera: &mut Camera, movement: &Movement, dt: f64) {
+        Self::turn(camera, movement.turn * TURN_SPEED * dt, 0.0, movement.roll * TURN_SPEED * dt);
+        let distance = movement.distance(dt);
+        camera.position += distance.x * camera.forward() - distance.y * camera.left() + distance.z * camera.up();
+    }
+}
+
+/// Circles around a target point, always looking at it, with moving forwards and backwards zooming in
+/// and out, and moving sideways or up and down going around the target.  The camera can't roll
+#[derive(Copy, Clone, Debug)]
+pub struct Orbit {
+    pub target: Point3<f64>,
+    pub distance: f64,
+    /// The angles in degrees the camera is looking at the target from
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+impl Orbit {
+    /// The closest the camera can get to the target
+    const MIN_DISTANCE: f64 = 1.0;
+
+    pub fn new(target: Point3<f64>) -> Self {
+        Self {
+            target,
+            distance: 100.0,
+            pitch: 0.0,
+            yaw: 0.0,
+        }
+    }
+
+    /// Put the camera on the orbit, looking at the target
+    fn place(&self, camera: &mut Camera) {
+        camera.set_euler_angles(Vector3::new(self.pitch, self.yaw, 0.0));
+        camera.position = self.target - self.distance * camera.forward();
+    }
+}
+
+impl Controller for Orbit {
+    fn name(&self) -> &'static str {
+        "orbit"
+    }
+
+    fn attach(&mut self, camera: &mut Camera) {
+        let offset = self.target - camera.position;
+        if offset.norm() >= Self::MIN_DISTANCE {
+            let direction = offset.normalize();
+            self.distance = offset.norm();
+            self.pitch = (-direction.y).asin().to_degrees().clamp(-MAX_PITCH, MAX_PITCH);
+            self.yaw = (-direction.x).atan2(direction.z).to_degrees();
+        }
+        self.place(camera);
+    }
+
+    fn look(&mut self, camera: &mut Camera, delta: [f64; 2]) {
+        self.yaw += delta[0];
+        self.pitch = (self.pitch + delta[1]).clamp(-MAX_PITCH, MAX_PITCH);
+        self.place(camera);
+    }
+
+    fn update(&mut self, camera: &mut Camera, movement: &Movement, dt: f64) {
+        let circle = movement.speed * TURN_SPEED * dt;
+        self.yaw += movement.turn * TURN_SPEED * dt - movement.right * circle;
+        self.pitch = (self.pitch + movement.up * circle).clamp(-MAX_PITCH, MAX_PITCH);
+        self.distance = (self.distance - movement.distance(dt).x).max(Self::MIN_DISTANCE);
+        self.place(camera);
+    }
+}
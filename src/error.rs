@@ -0,0 +1,108 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::error::Error;
+
+use image::ImageError;
+
+#[derive(Debug)]
+pub enum LoadErrorKind {
+    Io(io::Error),
+    /// An image that couldn't be read or decoded, such as a texture
+    Image(ImageError),
+    /// A token that could not be parsed as the expected type
+    Parse(String),
+    /// A face index that refers to an element that doesn't exist (yet)
+    IndexOutOfRange {
+        index: isize,
+        count: usize,
+    },
+    /// An element with the wrong number of components, such as a vertex with only two coordinates
+    WrongArity {
+        element: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A reference to something that hasn't been defined, such as a scene node using an unknown model
+    UnknownName {
+        element: &'static str,
+        name: String,
+    },
+    /// A statement that isn't allowed where it appears, such as a node property outside of a node
+    Unexpected(String),
+    /// An error loading another file that this one refers to
+    Referenced(Box<LoadError>),
+}
+
+#[derive(Debug)]
+pub struct LoadError {
+    pub filename: String,
+    /// The 1-based line number the error occurred on, if the error is tied to a specific line
+    pub line: Option<usize>,
+    pub kind: LoadErrorKind,
+}
+
+impl LoadError {
+    pub fn new(filename: impl AsRef<Path>, line: Option<usize>, kind: LoadErrorKind) -> Self {
+        Self {
+            filename: filename.as_ref().display().to_string(),
+            line,
+            kind,
+        }
+    }
+}
+
+impl From<io::Error> for LoadErrorKind {
+    fn from(err: io::Error) -> Self {
+        LoadErrorKind::Io(err)
+    }
+}
+
+impl fmt::Display for LoadErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadErrorKind::Io(err) => write!(f, "{}", err),
+            LoadErrorKind::Image(err) => write!(f, "{}", err),
+            LoadErrorKind::Parse(token) => write!(f, "unable to parse {:?}", token),
+            LoadErrorKind::IndexOutOfRange {
+                index,
+                count,
+            } => write!(f, "index {} is out of range, only {} elements defined", index, count),
+            LoadErrorKind::WrongArity {
+                element,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected at least {} values for {:?} but found {}",
+                expected, element, found
+            ),
+            LoadErrorKind::UnknownName {
+                element,
+                name,
+            } => write!(f, "unknown {} {:?}", element, name),
+            LoadErrorKind::Unexpected(statement) => write!(f, "unexpected {}", statement),
+            LoadErrorKind::Referenced(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.filename, line, self.kind),
+            None => write!(f, "{}: {}", self.filename, self.kind),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Io(err) => Some(err),
+            LoadErrorKind::Image(err) => Some(err),
+            LoadErrorKind::Referenced(err) => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use opengl_graphics::GlyphCache;
+use piston_window::TextureSettings;
+
+/// A font built into the binary, so there's always something to draw text with
+const FALLBACK_FONT: &[u8] = include_bytes!("../data/fonts/DejaVuSansMono.ttf");
+
+/// The fonts to look for in the system font directories, in order of preference
+const PREFERRED_FONTS: &[&str] = &[
+    "agave-r-autohinted.ttf",
+    "DejaVuSansMono.ttf",
+    "LiberationMono-Regular.ttf",
+    "UbuntuMono-R.ttf",
+    "NotoSansMono-Regular.ttf",
+    "Menlo.ttf",
+    "consola.ttf",
+    "DejaVuSans.ttf",
+    "LiberationSans-Regular.ttf",
+    "Arial.ttf",
+    "arial.ttf",
+];
+
+/// How many directories deep to look for fonts, since most systems sort them into subdirectories
+const MAX_SEARCH_DEPTH: usize = 4;
+
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(home) = env::var_os("HOME") {
+        let home = PathBuf::from(home);
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+        dirs.push(home.join("Library/Fonts"));
+    }
+    dirs.push(PathBuf::from("/usr/share/fonts"));
+    dirs.push(PathBuf::from("/usr/local/share/fonts"));
+    dirs.push(PathBuf::from("/Library/Fonts"));
+    dirs.push(PathBuf::from("/System/Library/Fonts"));
+    if let Some(windir) = env::var_os("WINDIR") {
+        dirs.push(PathBuf::from(windir).join("Fonts"));
+    }
+    dirs
+}
+
+fn find_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if depth < MAX_SEARCH_DEPTH {
+                find_files(&path, depth + 1, files);
+            }
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Search the common system font directories for fonts with any of the given file names, returning
+/// what's found in the same order as the names
+pub fn find_fonts(names: &[&str]) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in font_dirs() {
+        find_files(&dir, 0, &mut files);
+    }
+
+    names
+        .iter()
+        .flat_map(|name| {
+            files
+                .iter()
+                .filter(move |path| path.file_name().is_some_and(|file_name| file_name == *name))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Load the glyphs for the requested font, given either as a path or the file name of a system font,
+/// or else the first usable font from the list of preferred fonts, falling back to the built-in font
+/// if nothing else can be loaded.  The system font directories are only searched if the requested
+/// path doesn't load, and then only once for every name
+pub fn load_glyphs(requested: Option<&str>) -> GlyphCache<'static> {
+    let load = |path: &Path| match GlyphCache::new(path, (), TextureSettings::new()) {
+        Ok(glyphs) => Some(glyphs),
+        Err(err) => {
+            eprintln!("warning: unable to load font {}: {}", path.display(), err);
+            None
+        },
+    };
+
+    // a font asked for by file name is searched for along with the preferred fonts
+    let mut wanted = None;
+    match requested {
+        Some(requested) if Path::new(requested).is_file() => {
+            if let Some(glyphs) = load(Path::new(requested)) {
+                return glyphs;
+            }
+        },
+        Some(requested) => wanted = Some(requested),
+        None => {},
+    }
+
+    let names: Vec<&str> = wanted.into_iter().chain(PREFERRED_FONTS.iter().copied()).collect();
+    let found = find_fonts(&names);
+    if let Some(wanted) = wanted {
+        if !found
+            .iter()
+            .any(|path| path.file_name().is_some_and(|file_name| file_name == wanted))
+        {
+            eprintln!("warning: unable to find font {:?}", wanted);
+        }
+    }
+    for path in found {
+        if let Some(glyphs) = load(&path) {
+            return glyphs;
+        }
+    }
+
+    GlyphCache::from_bytes(FALLBACK_FONT, (), TextureSettings::new()).expect("the built-in font should always load")
+}
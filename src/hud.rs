@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
+
+use piston_window::{rectangle, Context, Graphics, Line};
+
+use rather3d::scene::Model;
+
+/// How many frames the frame time graph covers
+const HISTORY: usize = 120;
+/// The frame time at the top of the graph, in seconds, where longer frames are cut off
+const GRAPH_MAX: f64 = 0.05;
+/// The frame time to aim for, which is marked across the graph
+const TARGET_FRAME_TIME: f64 = 1.0 / 60.0;
+const GRAPH_SIZE: [f64; 2] = [240.0, 60.0];
+const GRAPH_BACKGROUND: [f32; 4] = [0.0, 0.0, 0.0, 0.1];
+const GRAPH_BARS: [f32; 4] = [0.0, 0.0, 1.0, 0.8];
+const GRAPH_TARGET: [f32; 4] = [1.0, 0.0, 0.0, 0.8];
+
+/// The times between the last few frames
+#[derive(Clone, Debug, Default)]
+pub struct FrameTimes {
+    times: VecDeque<Duration>,
+    last: Option<Instant>,
+}
+
+impl FrameTimes {
+    /// Note that a new frame is being drawn
+    pub fn tick(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last {
+            self.times.push_back(now - last);
+            if self.times.len() > HISTORY {
+                self.times.pop_front();
+            }
+        }
+        self.last = Some(now);
+    }
+
+    /// The average frames per second over the history
+    pub fn fps(&self) -> f64 {
+        let total: Duration = self.times.iter().sum();
+        if total.is_zero() {
+            0.0
+        } else {
+            self.times.len() as f64 / total.as_secs_f64()
+        }
+    }
+
+    /// How long the last frame took
+    pub fn latest(&self) -> Duration {
+        self.times.back().copied().unwrap_or_default()
+    }
+
+    /// Draw the frame times as a bar graph with the top left corner at the given position, oldest on
+    /// the left, with a line across it at the frame time needed for 60 frames per second
+    pub fn draw_graph<G: Graphics>(&self, position: [f64; 2], c: &Context, g: &mut G) {
+        let [x, y] = position;
+        let [width, height] = GRAPH_SIZE;
+        rectangle(GRAPH_BACKGROUND, [x, y, width, height], c.transform, g);
+
+        let bar_width = width / HISTORY as f64;
+        for (i, time) in self.times.iter().enumerate() {
+            let bar_height = (time.as_secs_f64() / GRAPH_MAX).min(1.0) * height;
+            let bar_x = x + i as f64 * bar_width;
+            rectangle(
+                GRAPH_BARS,
+                [bar_x, y + height - bar_height, bar_width, bar_height],
+                c.transform,
+                g,
+            );
+        }
+
+        let target_y = y + height - TARGET_FRAME_TIME / GRAPH_MAX * height;
+        Line::new(GRAPH_TARGET, 0.5).draw_from_to([x, target_y], [x + width, target_y], &c.draw_state, c.transform, g);
+    }
+}
+
+/// How big the models being drawn are, leaving out groups that are hidden
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub faces: usize,
+    pub edges: usize,
+    pub triangles: usize,
+}
+
+impl MeshStats {
+    /// The stats for the model's visible faces, which need working out again when a group is shown
+    /// or hidden
+    pub fn of(model: &Model) -> Self {
+        let visible = model.object.face_visibility();
+        Self {
+            vertices: model.object.used_point_count(),
+            faces: model.object.visible_faces().count(),
+            edges: model.object.edge_count(),
+            triangles: model.triangles.iter().filter(|triangle| visible[triangle.face]).count(),
+        }
+    }
+}
+
+impl AddAssign for MeshStats {
+    fn add_assign(&mut self, other: Self) {
+        self.vertices += other.vertices;
+        self.faces += other.faces;
+        self.edges += other.edges;
+        self.triangles += other.triangles;
+    }
+}
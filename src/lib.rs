@@ -0,0 +1,12 @@
+pub mod camera;
+pub mod clip;
+pub mod controller;
+pub mod error;
+pub mod lighting;
+pub mod material;
+pub mod math;
+pub mod mesh;
+pub mod render;
+pub mod scene;
+pub mod texture;
+pub mod triangulate;
@@ -0,0 +1,328 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3};
+
+use crate::material::Material;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Light {
+    /// Light that reaches every surface equally
+    Ambient { color: Vector3<f64> },
+    /// Light from far away, such as the sun, that shines in the given direction
+    Directional { direction: Vector3<f64>, color: Vector3<f64> },
+}
+
+impl Light {
+    /// Turn a directional light right around the vertical axis and then down by the given degrees,
+    /// stopping just short of vertical so it can always be turned back.  Ambient lights have no
+    /// direction, so they're unchanged
+    pub fn turn(&mut self, right: f64, down: f64) {
+        if let Light::Directional {
+            direction,
+            ..
+        } = self
+        {
+            let length = direction.norm();
+            if length == 0.0 {
+                return;
+            }
+            let azimuth = direction.x.atan2(direction.z) - right.to_radians();
+            let elevation = ((-direction.y / length).asin() + down.to_radians()).clamp(-89_f64.to_radians(), 89_f64.to_radians());
+            *direction = length
+                * Vector3::new(
+                    azimuth.sin() * elevation.cos(),
+                    -elevation.sin(),
+                    azimuth.cos() * elevation.cos(),
+                );
+        }
+    }
+}
+
+/// The lights to use when a scene doesn't have any: a dim ambient light, and a brighter light
+/// shining down from over the camera's left shoulder
+pub fn default_lights() -> Vec<Light> {
+    vec![
+        Light::Ambient {
+            color: Vector3::new(0.3, 0.3, 0.3),
+        },
+        Light::Directional {
+            direction: Vector3::new(-1.0, -2.0, 1.0),
+            color: Vector3::new(0.7, 0.7, 0.7),
+        },
+    ]
+}
+
+/// How the highlights where lights reflect off shiny surfaces are worked out
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Specular {
+    /// No highlights, only diffuse light
+    None,
+    /// Compare the direction of the reflected light with the direction to the eye
+    Phong,
+    /// Compare the normal with the direction halfway between the light and the eye, which gives
+    /// wider highlights and is cheaper to work out
+    BlinnPhong,
+}
+
+/// The parts of a material that affect how it's lit
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Surface {
+    pub color: [f32; 4],
+    pub specular: Vector3<f64>,
+    /// The specular exponent, where higher values give smaller, sharper highlights.  Zero means
+    /// the surface has no highlights at all
+    pub shininess: f64,
+}
+
+impl Surface {
+    /// A surface in a plain color, without highlights
+    pub fn matte(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            specular: Vector3::zeros(),
+            shininess: 0.0,
+        }
+    }
+
+    pub fn from_material(material: &Material) -> Self {
+        Self {
+            color: material.diffuse_color(),
+            specular: material.specular,
+            shininess: material.shininess,
+        }
+    }
+}
+
+/// The lights falling on a model, along with how to turn its points and normals into world space
+#[derive(Clone, Debug)]
+pub struct Lighting<'a> {
+    pub lights: &'a [Light],
+    pub world_from_model: Matrix4<f64>,
+    /// The inverse transpose of the model's transform, which keeps normals perpendicular to the
+    /// surface even when the transform doesn't preserve angles
+    pub normal_matrix: Matrix3<f64>,
+    /// Where the surfaces are seen from in world space, for specular highlights
+    pub eye: Point3<f64>,
+}
+
+impl<'a> Lighting<'a> {
+    pub fn new(lights: &'a [Light], world_from_model: &Matrix4<f64>, eye: Point3<f64>) -> Self {
+        let linear = world_from_model.fixed_view::<3, 3>(0, 0).into_owned();
+        Self {
+            lights,
+            world_from_model: *world_from_model,
+            normal_matrix: linear.try_inverse().unwrap_or(linear).transpose(),
+            eye,
+        }
+    }
+
+    /// Turn a point in the model's space into world space
+    pub fn world_point(&self, point: &Point3<f64>) -> Point3<f64> {
+        self.world_from_model.transform_point(point)
+    }
+
+    /// Turn a normal in the model's space into a unit normal in world space
+    pub fn world_normal(&self, normal: &Vector3<f64>) -> Vector3<f64> {
+        (self.normal_matrix * normal)
+            .try_normalize(0.0)
+            .unwrap_or_else(Vector3::zeros)
+    }
+
+    /// The light falling on a surface with the given unit normal at the given point in world space,
+    /// from the ambient lights and any directional lights shining on its front (Lambert's cosine
+    /// law), plus the highlights from those lights
+    pub fn illuminate(&self, surface: &Surface, normal: &Vector3<f64>, position: &Point3<f64>, specular: Specular) -> Illumination {
+        let towards_eye = (self.eye - position).try_normalize(0.0).unwrap_or_else(Vector3::zeros);
+        let shiny = specular != Specular::None && surface.shininess > 0.0;
+
+        let mut diffuse_light = Vector3::zeros();
+        let mut specular_light = Vector3::zeros();
+        for source in self.lights {
+            match source {
+                Light::Ambient {
+                    color,
+                } => diffuse_light += color,
+                Light::Directional {
+                    direction,
+                    color,
+                } => {
+                    let towards_light = -direction.try_normalize(0.0).unwrap_or_else(Vector3::zeros);
+                    let cosine = normal.dot(&towards_light);
+                    if cosine <= 0.0 {
+                        continue;
+                    }
+                    diffuse_light += color * cosine;
+
+                    if shiny {
+                        let alignment = match specular {
+                            Specular::Phong => (2.0 * cosine * normal - towards_light).dot(&towards_eye),
+                            _ => (towards_light + towards_eye)
+                                .try_normalize(0.0)
+                                .map_or(0.0, |halfway| normal.dot(&halfway)),
+                        };
+                        specular_light += color * alignment.max(0.0).powf(surface.shininess);
+                    }
+                },
+            }
+        }
+
+        Illumination {
+            diffuse: diffuse_light,
+            specular: surface.specular.component_mul(&specular_light),
+        }
+    }
+
+    /// The color of the surface at the given point, lit as described by `illuminate`.  The alpha is
+    /// left as it is
+    pub fn shade(&self, surface: &Surface, normal: &Vector3<f64>, position: &Point3<f64>, specular: Specular) -> [f32; 4] {
+        self.illuminate(surface, normal, position, specular).apply(surface.color)
+    }
+}
+
+/// The light reaching a point on a surface, split into the light that's scattered in the surface's
+/// own color, and the highlights that are reflected in the color of the lights
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Illumination {
+    pub diffuse: Vector3<f64>,
+    pub specular: Vector3<f64>,
+}
+
+impl Illumination {
+    /// Full light with no highlights, which leaves colors as they are
+    pub fn unlit() -> Self {
+        Self {
+            diffuse: Vector3::new(1.0, 1.0, 1.0),
+            specular: Vector3::zeros(),
+        }
+    }
+
+    /// The color of a surface with the given base color in this light.  The alpha is left as it is
+    pub fn apply(&self, color: [f32; 4]) -> [f32; 4] {
+        [
+            color[0] * self.diffuse.x as f32 + self.specular.x as f32,
+            color[1] * self.diffuse.y as f32 + self.specular.y as f32,
+            color[2] * self.diffuse.z as f32 + self.specular.z as f32,
+            color[3],
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Vector3<f64> = Vector3::new(1.0, 1.0, 1.0);
+
+    /// A directional light shining onto the origin from the given direction, seen from straight
+    /// above along +z
+    fn light_from(towards_light: Vector3<f64>) -> Vec<Light> {
+        vec![
+            Light::Ambient {
+                color: Vector3::new(0.25, 0.25, 0.25),
+            },
+            Light::Directional {
+                direction: -towards_light,
+                color: WHITE,
+            },
+        ]
+    }
+
+    fn illuminate(lights: &[Light], surface: &Surface, specular: Specular) -> Illumination {
+        let lighting = Lighting::new(lights, &Matrix4::identity(), Point3::new(0.0, 0.0, 10.0));
+        lighting.illuminate(surface, &Vector3::z(), &Point3::origin(), specular)
+    }
+
+    #[test]
+    fn diffuse_light_falls_off_with_the_cosine() {
+        let surface = Surface::matte([1.0; 4]);
+        for degrees in [0.0_f64, 30.0, 60.0, 85.0] {
+            let angle = degrees.to_radians();
+            let lights = light_from(Vector3::new(angle.sin(), 0.0, angle.cos()));
+            let diffuse = illuminate(&lights, &surface, Specular::None).diffuse;
+            assert!(
+                (diffuse.x - (0.25 + angle.cos())).abs() < 1e-9,
+                "{} degrees gave {}",
+                degrees,
+                diffuse.x
+            );
+        }
+    }
+
+    #[test]
+    fn light_from_behind_only_leaves_ambient() {
+        let surface = Surface::matte([1.0; 4]);
+        for towards_light in [Vector3::x(), -Vector3::z(), Vector3::new(0.0, 1.0, -1.0)] {
+            let illumination = illuminate(&light_from(towards_light), &surface, Specular::Phong);
+            assert_eq!(illumination.diffuse, Vector3::new(0.25, 0.25, 0.25));
+            assert_eq!(illumination.specular, Vector3::zeros());
+        }
+    }
+
+    #[test]
+    fn turning_stops_short_of_vertical() {
+        let mut light = Light::Directional {
+            direction: Vector3::new(0.0, 0.0, 2.0),
+            color: WHITE,
+        };
+        light.turn(0.0, 100.0);
+        let Light::Directional {
+            direction,
+            ..
+        } = light
+        else {
+            unreachable!()
+        };
+        assert!((direction.norm() - 2.0).abs() < 1e-9);
+        assert!((direction.y / 2.0 + 89_f64.to_radians().sin()).abs() < 1e-9);
+
+        light.turn(0.0, -300.0);
+        let Light::Directional {
+            direction,
+            ..
+        } = light
+        else {
+            unreachable!()
+        };
+        assert!((direction.y / 2.0 - 89_f64.to_radians().sin()).abs() < 1e-9);
+
+        // turning right shines towards -x, which is the screen's right
+        let mut light = Light::Directional {
+            direction: Vector3::z(),
+            color: WHITE,
+        };
+        light.turn(90.0, 0.0);
+        let Light::Directional {
+            direction,
+            ..
+        } = light
+        else {
+            unreachable!()
+        };
+        assert!((direction - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn blinn_phong_highlights_are_wider_than_phong() {
+        let surface = Surface {
+            color: [1.0; 4],
+            specular: WHITE,
+            shininess: 2.0,
+        };
+
+        // with the eye in the mirror direction, both give the full highlight
+        let lights = light_from(Vector3::z());
+        assert!((illuminate(&lights, &surface, Specular::Phong).specular - WHITE).norm() < 1e-9);
+        assert!((illuminate(&lights, &surface, Specular::BlinnPhong).specular - WHITE).norm() < 1e-9);
+
+        // 60 degrees away, Phong compares the reflection 120 degrees from the eye, and Blinn-Phong
+        // the normal 30 degrees from the halfway vector
+        let angle = 60_f64.to_radians();
+        let lights = light_from(Vector3::new(angle.sin(), 0.0, angle.cos()));
+        let phong = illuminate(&lights, &surface, Specular::Phong).specular;
+        let blinn_phong = illuminate(&lights, &surface, Specular::BlinnPhong).specular;
+        assert!((phong.x - 0.25).abs() < 1e-9, "{}", phong.x);
+        assert!((blinn_phong.x - 0.75).abs() < 1e-9, "{}", blinn_phong.x);
+
+        assert_eq!(illuminate(&lights, &surface, Specular::None).specular, Vector3::zeros());
+        let matte = Surface::matte([1.0; 4]);
+        assert_eq!(illuminate(&lights, &matte, Specular::Phong).specular, Vector3::zeros());
+    }
+}
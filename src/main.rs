@@ -1,296 +1,525 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::f64::consts::PI;
+use std::collections::HashSet;
+use std::env;
+use std::path::PathBuf;
+use std::process;
 
-use nalgebra::{Point3, Vector3, Vector4, Matrix4, Perspective3};
+use nalgebra::{Point3, Vector3};
 use piston_window::{
-    PistonWindow, WindowSettings, clear, Line, Text, DrawState, EventLoop, Events, EventSettings, RenderEvent, Button, Key,
-    PressEvent, ReleaseEvent, MouseRelativeEvent, ResizeEvent, IdleEvent, TextureSettings,
+    PistonWindow, WindowSettings, clear, Line, Text, Events, EventSettings, RenderEvent, Button, PressEvent, ReleaseEvent,
+    MouseRelativeEvent, ResizeEvent, UpdateEvent, FocusEvent, TextureSettings, ImageSize, Window, AdvancedWindow,
 };
-use opengl_graphics::{GlGraphics, OpenGL, GlyphCache};
+use opengl_graphics::{GlGraphics, OpenGL, Texture};
 
-#[derive(Clone, Debug)]
-struct Object {
-    points: Vec<Point3<f64>>,
-    faces: Vec<Vec<usize>>,
-}
+use rather3d::camera::{Camera, Projection};
+use rather3d::controller::{Controller, FirstPerson, FreeFly, Movement, Orbit};
+use rather3d::error::LoadError;
+use rather3d::lighting::{default_lights, Light};
+use rather3d::render::{self, Framebuffer, RenderMode, RenderOptions, RenderStats, Shading};
+use rather3d::scene::{Model, Node, Scene, Transform};
+use rather3d::texture::Filter;
 
-impl Object {
-    pub fn read(filename: &str) -> Result<Object, io::Error> {
-        let file = File::open(filename)?;
-        let reader = BufReader::new(file);
-
-        let mut points = vec![];
-        let mut faces = vec![];
-
-        for line in reader.lines() {
-            let line = line?;
-            let mut words = line.split_whitespace();
-            if let Some(line_type) = words.next() {
-                match line_type {
-                    "v" => {
-                        let point: Point3<f64> = Vector3::from_iterator(words.map(|w| str::parse::<f64>(w).unwrap())).into();
-                        points.push(point);
-                    },
-                    "f" => {
-                        let face: Vec<usize> = words.map(|w| str::parse::<usize>(w).unwrap()).collect();
-                        faces.push(face);
-                    },
-                    _ => {},
-                }
-            }
+mod bindings;
+mod font;
+mod hud;
+mod options;
+
+use crate::bindings::{Action, Bindings};
+use crate::hud::{FrameTimes, MeshStats};
+use crate::options::{Options, OptionsError};
+
+const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+const SIZE_X: f64 = 1920.0;
+const SIZE_Y: f64 = 1080.0;
+
+/// The scene to show when no models or scene are given
+const DEFAULT_SCENE: &str = "data/cessna.scene";
+/// How many degrees a light turns for each key press
+const LIGHT_STEP: f64 = 5.0;
+/// Where to save a scene that wasn't loaded from a file given with --scene
+const SAVED_SCENE: &str = "rather3d.scene";
+/// The space left between models placed side by side
+const MODEL_SPACING: f64 = 10.0;
+/// How much faster the camera moves while the fast key is held
+const FAST_SPEED: f64 = 4.0;
+/// How much slower the camera moves while the slow key is held
+const SLOW_SPEED: f64 = 0.25;
+/// The start of the filenames screenshots are saved to, which are numbered from 1
+const SCREENSHOT_PREFIX: &str = "screenshot";
+/// How many times a second the camera and the scene's animations are moved on, whatever the frame rate
+const UPDATES_PER_SECOND: u64 = 120;
+
+/// Load each model into the scene, starting in front of the camera and placing the rest side by side
+fn add_models(scene: &mut Scene, filenames: &[String]) -> Result<(), LoadError> {
+    let mut position = Vector3::new(0.0, 0.0, 100.0);
+    let mut previous_max = None;
+
+    for filename in filenames {
+        // model names are single words in scene files
+        let name = filename.split_whitespace().collect::<Vec<&str>>().join("_");
+        let model = Model::read(&name, filename)?;
+        let (min, max) = model.object.bounds().unwrap_or((Point3::origin(), Point3::origin()));
+        if let Some(previous_max) = previous_max {
+            position.x += previous_max - min.x + MODEL_SPACING;
         }
+        previous_max = Some(max.x);
 
-        Ok(Object {
-            points,
-            faces,
-        })
+        let node = Node::new(filename, Transform::from_translation(position)).with_model(scene.add_model(model));
+        scene.nodes.push(node);
     }
+    Ok(())
+}
 
-    pub fn project(
-        &self,
-        camera_position: Point3<f64>,
-        camera_orientation: Vector3<f64>,
-        window_size: [f64; 2],
-    ) -> Vec<Point3<f64>> {
-        let object_position = Point3::new(0.0, 0.0, 100.0);
-
-        let scale = Self::scale(1.0);
-        let rotate_z = Self::rotate_z(0.0);
-        let rotate_y = Self::rotate_y(0.0);
-        //let translate = Self::translate(800.0, 800.0, -1000.0);
-        let translate = Self::translate(object_position);
-        let world_from_object = translate * scale * rotate_y * rotate_z;
-
-        //let perspective_from_camera = Self::perspective_transform_fov(PI / 4.0, 1.0, 0.1, 5000.0);
-        let perspective_from_camera = Self::perspective_transform_fov(PI / 4.0, window_size[0] / window_size[1], 1.0, 10000.0);
-        //let perspective_from_camera = Perspective3::new(16.0 / 9.0, 3.14 / 4.0, 1.0, 10000.0).to_homogeneous();
-        //let perspective_from_camera = Perspective3::new(window_size[0] / window_size[1], 3.14 / 4.0, 1.0, 10000.0).to_homogeneous();
-
-        let camera_from_world = Self::rotate(camera_orientation) * Self::translate(-1.0 * camera_position);
-
-        self.points
-            .iter()
-            .map(|point| point.to_homogeneous())
-            .map(|point| perspective_from_camera * camera_from_world * world_from_object * point)
-            .map(|point| Point3::from_homogeneous(point).unwrap())
-            //.map(|point| translate * scale * rotate_y * rotate_z * point.to_homogeneous())
-            //.map(|point| Point3::new(point[0], point[1], point[2]))
-            //.map(|point| perspective_from_camera.project_point(&point))
-            .collect()
+/// How the keys and buttons being held down ask the camera to move.  Opposite actions cancel each
+/// other out
+fn held_movement(bindings: &Bindings, held: &HashSet<Button>) -> Movement {
+    let axis = |positive: Action, negative: Action| match (bindings.is_held(positive, held), bindings.is_held(negative, held)) {
+        (true, false) => 1.0,
+        (false, true) => -1.0,
+        _ => 0.0,
+    };
+
+    let mut speed = 1.0;
+    if bindings.is_held(Action::Fast, held) {
+        speed *= FAST_SPEED;
     }
-
-    #[rustfmt::skip]
-    pub fn scale(scale: f64) -> Matrix4<f64> {
-        Matrix4::new(
-            scale,   0.0,   0.0, 0.0,
-              0.0, scale,   0.0, 0.0,
-              0.0,   0.0, scale, 0.0,
-              0.0,   0.0,   0.0, 1.0,
-        )
+    if bindings.is_held(Action::Slow, held) {
+        speed *= SLOW_SPEED;
     }
 
-    #[rustfmt::skip]
-    pub fn translate(point: Point3<f64>) -> Matrix4<f64> {
-        Matrix4::new(
-              1.0,   0.0,   0.0,   point[0],
-              0.0,   1.0,   0.0,   point[1],
-              0.0,   0.0,   1.0,   point[2],
-              0.0,   0.0,   0.0, 1.0,
-        )
+    Movement {
+        forward: axis(Action::MoveForward, Action::MoveBack),
+        right: axis(Action::StrafeRight, Action::StrafeLeft),
+        up: axis(Action::MoveUp, Action::MoveDown),
+        turn: axis(Action::TurnRight, Action::TurnLeft),
+        roll: axis(Action::RollRight, Action::RollLeft),
+        speed,
     }
+}
 
-    pub fn rotate(vector: Vector3<f64>) -> Matrix4<f64> {
-        Self::rotate_x(vector[0]) * Self::rotate_y(vector[1]) * Self::rotate_z(vector[2])
-    }
+/// The first screenshot filename that isn't already taken
+fn screenshot_path() -> PathBuf {
+    (1..)
+        .map(|i| PathBuf::from(format!("{}-{}.png", SCREENSHOT_PREFIX, i)))
+        .find(|path| !path.exists())
+        .unwrap()
+}
 
-    #[rustfmt::skip]
-    pub fn rotate_x(x: f64) -> Matrix4<f64> {
-        let x = x * PI / 180.0;
-        Matrix4::new(
-            1.0,        0.0,     0.0, 0.0,
-            0.0,    x.cos(), x.sin(), 0.0,
-            0.0, -(x.sin()), x.cos(), 0.0,
-            0.0,        0.0,     0.0, 1.0,
-        )
+/// A short description of a light for the overlay
+fn describe_light(index: usize, light: &Light) -> String {
+    match light {
+        Light::Ambient {
+            ..
+        } => format!("light {}: ambient", index),
+        Light::Directional {
+            direction,
+            ..
+        } => format!(
+            "light {}: directional ({:.2}, {:.2}, {:.2})",
+            index, direction.x, direction.y, direction.z
+        ),
     }
+}
 
-    #[rustfmt::skip]
-    pub fn rotate_y(y: f64) -> Matrix4<f64> {
-        let y = y * PI / 180.0;
-        Matrix4::new(
-               y.cos(),     0.0, y.sin(), 0.0,
-                   0.0,     1.0,     0.0, 0.0,
-            -(y.sin()),     0.0, y.cos(), 0.0,
-                   0.0,     0.0,     0.0, 1.0,
-        )
-    }
+/// Every group in the scene, as the index of its model and its index in that model
+fn scene_groups(scene: &Scene) -> Vec<(usize, usize)> {
+    scene
+        .models
+        .iter()
+        .enumerate()
+        .flat_map(|(i, model)| (0..model.object.groups.len()).map(move |j| (i, j)))
+        .collect()
+}
 
-    #[rustfmt::skip]
-    pub fn rotate_z(z: f64) -> Matrix4<f64> {
-        let z = z * PI / 180.0;
-        Matrix4::new(
-               z.cos(), z.sin(), 0.0, 0.0,
-            -(z.sin()), z.cos(), 0.0, 0.0,
-                   0.0,     0.0, 1.0, 0.0,
-                   0.0,     0.0, 0.0, 1.0,
-        )
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(OptionsError::Help) => {
+            println!("{}", options::USAGE);
+            return;
+        },
+        Err(err) => {
+            eprintln!("error: {}\n\n{}", err, options::USAGE);
+            process::exit(1);
+        },
+    };
+
+    let bindings = match Bindings::load(options.bindings.as_deref()) {
+        Ok(bindings) => bindings,
+        Err(err) => {
+            eprintln!("error loading bindings: {}", err);
+            process::exit(1);
+        },
+    };
+
+    let scene_file = match &options.scene {
+        Some(scene_file) => Some(scene_file.as_str()),
+        None if options.models.is_empty() => Some(DEFAULT_SCENE),
+        None => None,
+    };
+    let mut scene = match scene_file.map(Scene::read).unwrap_or_else(|| Ok(Scene::new())) {
+        Ok(scene) => scene,
+        Err(err) => {
+            eprintln!("error loading scene: {}", err);
+            process::exit(1);
+        },
+    };
+    if let Err(err) = add_models(&mut scene, &options.models) {
+        eprintln!("error loading object: {}", err);
+        process::exit(1);
     }
-
-    #[rustfmt::skip]
-    pub fn perspective_transform_fov(fov: f64, aspect: f64, n: f64, f: f64) -> Matrix4<f64> {
-        let e = 1.0 / (fov / 2.0).tan();
-        Matrix4::new(
-          e / aspect,   0.0,                 0.0,                       0.0,
-                 0.0,   e,                   0.0,                       0.0,
-                 0.0,   0.0,   (f + n) / (n - f),   (2.0 * f * n) / (n - f),
-                 0.0,   0.0,                -1.0,                       0.0,
-        )
+    for model in &scene.models {
+        let object = &model.object;
+        println!(
+            "loaded {}: {} points, {} texcoords, {} normals, {} faces, {} materials, {} groups, {} smoothing groups",
+            model.name,
+            object.points.len(),
+            object.texcoords.len(),
+            object.normals.len(),
+            object.faces.len(),
+            object.materials.len(),
+            object.groups.len(),
+            object.smoothing_groups().len()
+        );
     }
-}
 
-const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+    // the projection given on the command line applies to every camera, not just the default one.
+    // It's only applied to the camera being looked through, so saving the scene keeps the
+    // projection each camera had in the scene file
+    let look_through = |scene_camera: &Camera, camera_name: &str| match options.projection.apply(&scene_camera.projection) {
+        Ok(projection) => Camera {
+            projection,
+            ..*scene_camera
+        },
+        Err(err) => {
+            eprintln!("error: {} with the {} camera", err, camera_name);
+            process::exit(1);
+        },
+    };
+    for (name, camera) in &scene.cameras {
+        look_through(camera, name);
+    }
 
-const SIZE_X: f64 = 1920.0;
-const SIZE_Y: f64 = 1080.0;
+    // start from the scene's first camera, if it has any
+    let mut scene_camera = 0;
+    let mut camera = match scene.cameras.first() {
+        Some((name, camera)) => look_through(camera, name),
+        None => look_through(&Camera::new(Projection::default()), "default"),
+    };
+    let mut render_options = RenderOptions {
+        mode: options.mode,
+        shading: options.shading,
+        cull_backfaces: options.cull_backfaces,
+        default_color: BLUE,
+        texture_filter: options.texture_filter,
+        show_triangles: false,
+    };
+    // give the scene its own copy of the default lights, so they can be moved around
+    if scene.lights.is_empty() {
+        scene.lights = default_lights();
+    }
 
-fn get_point(points: &[Point3<f64>], face: usize, window_size: [f64; 2]) -> ([f64; 2], bool) {
-    (
-        [
-            ((points[face - 1][0] + 1.0) / 2.0) * window_size[0],
-            ((points[face - 1][1] + 1.0) / 2.0) * window_size[1],
-        ],
-        points[face - 1][2] < 1.0,
-    )
-}
+    // render a single frame from the starting position to an image, without opening a window
+    if let Some(output) = &options.output {
+        let [width, height] = options.window_size;
+        let mut framebuffer = Framebuffer::new(width as usize, height as usize, [1.0; 4]);
+        render::render(&mut framebuffer, &scene, &camera, &render_options);
+        if let Err(err) = framebuffer.save_png(output) {
+            eprintln!("error saving image: {}", err);
+            process::exit(1);
+        }
+        return;
+    }
 
-fn main() {
     let opengl = OpenGL::V3_2;
-    let mut window: PistonWindow = WindowSettings::new("Hello Piston!", [SIZE_X, SIZE_Y])
-        .exit_on_esc(true)
+    let mut window: PistonWindow = WindowSettings::new("Hello Piston!", options.window_size)
+        .exit_on_esc(false)
         .graphics_api(opengl)
         .build()
         .unwrap();
 
-    let ref mut gl = GlGraphics::new(opengl);
+    let gl = &mut GlGraphics::new(opengl);
 
-    let object = Object::read("data/cessna.obj").unwrap();
-    //let object = Object::read("data/diamond.obj").unwrap();
+    let mut glyphs = font::load_glyphs(options.font.as_deref());
 
-    let font = "/usr/share/fonts/truetype/agave/agave-r-autohinted.ttf";
-    let mut glyphs = GlyphCache::new(font, (), TextureSettings::new()).unwrap();
-
-    let mut window_size = [SIZE_X, SIZE_Y];
-    let mut forward = 0.0;
-    let mut dry = 0.0;
+    let mut window_size = options.window_size;
+    let mut held_buttons = HashSet::new();
     let mut cursor = [0.0; 2];
-    let mut camera_position = Point3::new(0.0_f64, 0.0, 0.0);
-    let mut camera_orientation = Vector3::new(0.0_f64, 0.0, 0.0);
-
-    let mut events = Events::new(EventSettings::new());
+    let mut mouse_captured = false;
+    let mut selected_group = 0;
+    let mut selected_light = 0;
+    let mut show_hud = true;
+    let mut frame_times = FrameTimes::default();
+    let mut render_stats = RenderStats::default();
+    let mut model_stats: Vec<MeshStats> = scene.models.iter().map(MeshStats::of).collect();
+    let mut framebuffer = Framebuffer::new(window_size[0] as usize, window_size[1] as usize, [1.0; 4]);
+    let mut texture: Option<Texture> = None;
+    // the orbit circles the first model, which starts in front of the camera
+    let target = scene
+        .nodes
+        .first()
+        .map_or(Vector3::zeros(), |node| node.transform.translation);
+    let mut controllers: Vec<Box<dyn Controller>> = vec![
+        Box::new(FirstPerson::default()),
+        Box::new(Orbit::new(Point3::from(target))),
+        Box::new(FreeFly),
+    ];
+    let mut controller = 0;
+    controllers[controller].attach(&mut camera);
+
+    let mut event_settings = EventSettings::new();
+    event_settings.ups = UPDATES_PER_SECOND;
+    let mut events = Events::new(event_settings);
     while let Some(e) = events.next(&mut window) {
         e.resize(|args| {
             window_size = args.window_size;
         });
 
-        e.mouse_relative(|pos| {
-            cursor = pos;
-            camera_orientation.y += pos[0];
-            camera_orientation.x += pos[1];
-        });
+        // the mouse only looks around while it's captured, so it can be used for other things otherwise
+        if let Some(delta) = e.mouse_relative_args() {
+            cursor = delta;
+            if mouse_captured {
+                let vertical = if options.invert_y { -1.0 } else { 1.0 };
+                let turn = [delta[0] * options.sensitivity, delta[1] * options.sensitivity * vertical];
+                controllers[controller].look(&mut camera, turn);
+            }
+        }
+
+        // keys and buttons let go of while the window is in the background never send a release
+        if e.focus_args() == Some(false) {
+            held_buttons.clear();
+            mouse_captured = false;
+            window.set_capture_cursor(false);
+        }
 
-        if let Some(Button::Keyboard(key)) = e.press_args() {
-            match key {
-                Key::Up => {
-                    forward = -1.0;
+        if let Some(button) = e.press_args() {
+            held_buttons.insert(button);
+        }
+        let actions = e
+            .press_args()
+            .map_or(vec![], |button| bindings.pressed(button, &held_buttons));
+        let releasing_mouse = mouse_captured && actions.contains(&Action::ReleaseMouse);
+        for action in actions {
+            match action {
+                Action::CaptureMouse | Action::ReleaseMouse => {
+                    mouse_captured = action == Action::CaptureMouse;
+                    window.set_capture_cursor(mouse_captured);
                 },
-                Key::Down => {
-                    forward = 1.0;
+                Action::Quit if !releasing_mouse => {
+                    window.set_should_close(true);
                 },
-                Key::Left => {
-                    dry = 1.0;
+                Action::NextController => {
+                    controller = (controller + 1) % controllers.len();
+                    controllers[controller].attach(&mut camera);
                 },
-                Key::Right => {
-                    dry = -1.0;
+                Action::NextCamera if !scene.cameras.is_empty() => {
+                    scene_camera = (scene_camera + 1) % scene.cameras.len();
+                    let (name, next) = &scene.cameras[scene_camera];
+                    camera = look_through(next, name);
+                    controllers[controller].attach(&mut camera);
                 },
-                _ => {},
-            }
-        }
-
-        if let Some(button) = e.release_args() {
-            match button {
-                Button::Keyboard(Key::Up) | Button::Keyboard(Key::Down) => {
-                    forward = 0.0;
+                Action::SaveScene => {
+                    // keep where the camera is now as the scene's starting camera, with the
+                    // scene's own projection rather than the one from the command line
+                    match scene.cameras.first_mut() {
+                        Some((_, first)) => {
+                            first.position = camera.position;
+                            first.orientation = camera.orientation;
+                        },
+                        None => {
+                            let start = Camera {
+                                projection: Projection::default(),
+                                ..camera
+                            };
+                            scene.cameras.push(("start".to_string(), start));
+                        },
+                    }
+                    // only a scene given on the command line is saved over, never the default one
+                    let filename = options.scene.as_deref().unwrap_or(SAVED_SCENE);
+                    match scene.save(filename) {
+                        Ok(()) => println!("saved the scene to {}", filename),
+                        Err(err) => eprintln!("error saving the scene to {}: {}", filename, err),
+                    }
+                },
+                Action::Screenshot => {
+                    let mut screenshot = Framebuffer::new(window_size[0] as usize, window_size[1] as usize, [1.0; 4]);
+                    render::render(&mut screenshot, &scene, &camera, &render_options);
+                    let path = screenshot_path();
+                    match screenshot.save_png(&path) {
+                        Ok(()) => println!("saved a screenshot to {}", path.display()),
+                        Err(err) => eprintln!("error saving a screenshot to {}: {}", path.display(), err),
+                    }
+                },
+                Action::ToggleWireframe => {
+                    render_options.mode = match render_options.mode {
+                        RenderMode::Wireframe => RenderMode::Solid,
+                        RenderMode::Solid => RenderMode::Wireframe,
+                    };
+                },
+                Action::NextShading => {
+                    render_options.shading = match render_options.shading {
+                        Shading::Unlit => Shading::Flat,
+                        Shading::Flat => Shading::Gouraud,
+                        Shading::Gouraud => Shading::Phong,
+                        Shading::Phong => Shading::BlinnPhong,
+                        Shading::BlinnPhong => Shading::Unlit,
+                    };
+                },
+                Action::NextFilter => {
+                    render_options.texture_filter = match render_options.texture_filter {
+                        Filter::Nearest => Filter::Bilinear,
+                        Filter::Bilinear => Filter::Trilinear,
+                        Filter::Trilinear => Filter::Nearest,
+                    };
+                },
+                Action::NextLight => {
+                    selected_light = (selected_light + 1) % scene.lights.len().max(1);
+                },
+                Action::LightLeft | Action::LightRight | Action::LightUp | Action::LightDown => {
+                    let (right, down) = match action {
+                        Action::LightLeft => (-LIGHT_STEP, 0.0),
+                        Action::LightRight => (LIGHT_STEP, 0.0),
+                        Action::LightUp => (0.0, -LIGHT_STEP),
+                        _ => (0.0, LIGHT_STEP),
+                    };
+                    if let Some(light) = scene.lights.get_mut(selected_light) {
+                        light.turn(right, down);
+                    }
+                },
+                Action::ToggleCulling => {
+                    render_options.cull_backfaces = !render_options.cull_backfaces;
                 },
-                Button::Keyboard(Key::Left) | Button::Keyboard(Key::Right) => {
-                    dry = 0.0;
+                Action::ToggleTriangles => {
+                    render_options.show_triangles = !render_options.show_triangles;
+                },
+                Action::ToggleHud => {
+                    show_hud = !show_hud;
+                },
+                Action::NextGroup => {
+                    selected_group = (selected_group + 1) % scene_groups(&scene).len().max(1);
+                },
+                Action::ToggleGroup => {
+                    if let Some(&(model, group)) = scene_groups(&scene).get(selected_group) {
+                        let object = &mut scene.models[model].object;
+                        let (name, visible) = (object.groups[group].name.clone(), object.groups[group].visible);
+                        object.set_group_visible(&name, !visible);
+                        model_stats[model] = MeshStats::of(&scene.models[model]);
+                    }
                 },
                 _ => {},
             }
         }
 
-        camera_orientation.y -= dry;
-        camera_position.x += forward * camera_orientation.y.to_radians().sin();
-        camera_position.z -= forward * camera_orientation.y.to_radians().cos();
-        //println!("position: {:?}, orientation: {:?}", camera_position, camera_orientation);
+        if let Some(button) = e.release_args() {
+            held_buttons.remove(&button);
+        }
 
-        if let Some(args) = e.idle_args() {}
+        // the event loop sends updates at a steady rate, catching up if drawing falls behind
+        if let Some(args) = e.update_args() {
+            controllers[controller].update(&mut camera, &held_movement(&bindings, &held_buttons), args.dt);
+            scene.animate(args.dt);
+        }
 
         if let Some(args) = e.render_args() {
+            frame_times.tick();
             gl.draw(args.viewport(), |c, g| {
-                println!("start drawing");
-
-                Text::new_color(BLUE, 12)
-                    .draw_pos(
-                        &format!("mouse: {:?} {:?}", cursor[0], cursor[1]),
-                        [0.0, 24.0].into(),
-                        &mut glyphs,
-                        &c.draw_state,
-                        c.transform,
-                        g,
-                    )
-                    .unwrap();
-
-                Text::new_color(BLUE, 12)
-                    .draw_pos(
-                        &format!("position: {:?}, orientation: {:?}", camera_position, camera_orientation),
-                        [0.0, 12.0].into(),
-                        &mut glyphs,
-                        &c.draw_state,
-                        c.transform,
-                        g,
-                    )
-                    .unwrap();
-
                 clear([1.0; 4], g);
-                //rectangle(BLUE,
-                //          [0.0, 0.0, 100.0, 100.0],
-                //          c.transform, g);
-
-                let points = object.project(camera_position, camera_orientation, window_size);
-                //rotation += 4.0;
 
-                //println!("{:?}", points);
-
-                //Line::new(BLUE, 0.4)
-                //    .draw_from_to([0.0, 100.0], [100.0, 100.0], &c.draw_state, c.transform, g);
-                //Line::new(BLUE, 0.4)
-                //    .draw_from_to([100.0, 100.0], [100.0, 0.0], &c.draw_state, c.transform, g);
-
-                for face in &object.faces {
-                    let (p1, p1_clipped) = get_point(&points, face[0], window_size);
-                    let (p2, p2_clipped) = get_point(&points, face[1], window_size);
-                    let (p3, p3_clipped) = get_point(&points, face[2], window_size);
-
-                    if p1_clipped && p2_clipped && p3_clipped {
-                        continue;
+                if render_options.mode == RenderMode::Solid {
+                    let (width, height) = (window_size[0] as usize, window_size[1] as usize);
+                    if framebuffer.width() != width || framebuffer.height() != height {
+                        framebuffer = Framebuffer::new(width, height, [1.0; 4]);
                     }
+                    framebuffer.clear([1.0; 4]);
+                    render_stats = render::render(&mut framebuffer, &scene, &camera, &render_options);
 
-                    //println!("{:?} {:?} {:?}", p1, p2, p3);
+                    let image = framebuffer.to_image();
+                    match texture.as_mut() {
+                        Some(texture) if texture.get_size() == image.dimensions() => texture.update(&image),
+                        _ => texture = Some(Texture::from_image(&image, &TextureSettings::new())),
+                    }
+                    if let Some(texture) = &texture {
+                        piston_window::image(texture, c.transform, g);
+                    }
+                } else {
+                    render_stats = render::wireframe(&scene, &camera, window_size, &render_options, |from, to, color| {
+                        Line::new(color, 0.2).draw_from_to(from, to, &c.draw_state, c.transform, g)
+                    });
+                }
 
-                    Line::new(BLUE, 0.2).draw_from_to(p1, p2, &c.draw_state, c.transform, g);
-                    Line::new(BLUE, 0.2).draw_from_to(p2, p3, &c.draw_state, c.transform, g);
-                    Line::new(BLUE, 0.2).draw_from_to(p3, p1, &c.draw_state, c.transform, g);
+                if show_hud {
+                    let mut lines = vec![
+                        format!(
+                            "{}: position ({:.1}, {:.1}, {:.1}), pitch {:.1}, yaw {:.1}, roll {:.1}",
+                            controllers[controller].name(),
+                            camera.position.x,
+                            camera.position.y,
+                            camera.position.z,
+                            camera.euler_angles().x,
+                            camera.euler_angles().y,
+                            camera.euler_angles().z
+                        ),
+                        format!(
+                            "mouse: moved ({:.0}, {:.0}), {}",
+                            cursor[0],
+                            cursor[1],
+                            if mouse_captured { "captured" } else { "released" }
+                        ),
+                    ];
+                    if let Some(&(model, group)) = scene_groups(&scene).get(selected_group) {
+                        let group = &scene.models[model].object.groups[group];
+                        lines.push(format!(
+                            "group: {} ({})",
+                            group.name,
+                            if group.visible { "shown" } else { "hidden" }
+                        ));
+                    }
+                    let light = scene
+                        .lights
+                        .get(selected_light)
+                        .map_or("none".to_string(), |light| describe_light(selected_light, light));
+                    lines.push(format!(
+                        "shading: {:?}, filter: {:?}, {}",
+                        render_options.shading, render_options.texture_filter, light
+                    ));
+
+                    let mut mesh_stats = MeshStats::default();
+                    for instance in scene.instances() {
+                        mesh_stats += model_stats[instance.model];
+                    }
+                    lines.push(format!(
+                        "{:.0} fps, {:.1} ms per frame",
+                        frame_times.fps(),
+                        frame_times.latest().as_secs_f64() * 1000.0
+                    ));
+                    lines.push(format!(
+                        "{} vertices, {} faces, {} edges, {} triangles",
+                        mesh_stats.vertices, mesh_stats.faces, mesh_stats.edges, mesh_stats.triangles
+                    ));
+                    lines.push(format!(
+                        "{} {} drawn, {} culled ({} facing away, {} outside the view)",
+                        render_stats.drawn,
+                        if render_options.mode == RenderMode::Solid || render_options.show_triangles {
+                            "triangles"
+                        } else {
+                            "faces"
+                        },
+                        render_stats.culled(),
+                        render_stats.backfacing,
+                        render_stats.outside
+                    ));
+                    lines.push(format!(
+                        "projecting {:.1} ms, drawing {:.1} ms",
+                        render_stats.projecting.as_secs_f64() * 1000.0,
+                        render_stats.drawing.as_secs_f64() * 1000.0
+                    ));
+
+                    for (i, line) in lines.iter().enumerate() {
+                        Text::new_color(BLUE, 12)
+                            .draw_pos(line, [0.0, 12.0 * (i + 1) as f64], &mut glyphs, &c.draw_state, c.transform, g)
+                            .unwrap();
+                    }
+                    frame_times.draw_graph([0.0, 12.0 * lines.len() as f64 + 6.0], &c, g);
                 }
             });
         }
@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nalgebra::Vector3;
+
+use crate::error::{LoadError, LoadErrorKind};
+use crate::mesh::parse_floats;
+use crate::texture::{Filter, Texture};
+
+#[derive(Clone, Debug)]
+pub struct Material {
+    pub name: String,
+    /// Ambient color (`Ka`)
+    pub ambient: Vector3<f64>,
+    /// Diffuse color (`Kd`)
+    pub diffuse: Vector3<f64>,
+    /// Specular color (`Ks`)
+    pub specular: Vector3<f64>,
+    /// Specular exponent (`Ns`)
+    pub shininess: f64,
+    /// Opacity, where 1.0 is fully opaque (`d`, or `1 - Tr`)
+    pub dissolve: f64,
+    /// Diffuse texture (`map_Kd`), resolved relative to the material library
+    pub diffuse_map: Option<PathBuf>,
+    /// The diffuse texture loaded from `diffuse_map`, which is shared between copies of the material
+    pub diffuse_texture: Option<Arc<Texture>>,
+    /// How to sample the textures (`filter`, which isn't part of the MTL spec), or None to leave it to
+    /// the renderer
+    pub texture_filter: Option<Filter>,
+}
+
+impl Material {
+    /// Create a material with the defaults given by the MTL spec
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ambient: Vector3::new(0.2, 0.2, 0.2),
+            diffuse: Vector3::new(0.8, 0.8, 0.8),
+            specular: Vector3::new(1.0, 1.0, 1.0),
+            shininess: 0.0,
+            dissolve: 1.0,
+            diffuse_map: None,
+            diffuse_texture: None,
+            texture_filter: None,
+        }
+    }
+
+    /// The diffuse color as an RGBA color suitable for drawing
+    pub fn diffuse_color(&self) -> [f32; 4] {
+        [
+            self.diffuse[0] as f32,
+            self.diffuse[1] as f32,
+            self.diffuse[2] as f32,
+            self.dissolve as f32,
+        ]
+    }
+
+    /// Read all the materials defined in an MTL file
+    pub fn read_library(filename: &Path) -> Result<Vec<Material>, LoadError> {
+        let file = File::open(filename).map_err(|err| LoadError::new(filename, None, err.into()))?;
+        let reader = BufReader::new(file);
+        let dir = filename.parent().unwrap_or(Path::new(""));
+
+        let mut materials = vec![];
+        for (i, line) in reader.lines().enumerate() {
+            read_line(&mut materials, dir, line).map_err(|kind| LoadError::new(filename, Some(i + 1), kind))?;
+        }
+
+        Ok(materials)
+    }
+}
+
+fn read_line(materials: &mut Vec<Material>, dir: &Path, line: Result<String, io::Error>) -> Result<(), LoadErrorKind> {
+    let line = line?;
+    let mut words = line.split_whitespace();
+    let line_type = match words.next() {
+        Some(line_type) => line_type,
+        None => return Ok(()),
+    };
+
+    if line_type == "newmtl" {
+        let name = words.collect::<Vec<&str>>().join(" ");
+        materials.push(Material::new(&name));
+        return Ok(());
+    }
+
+    // any other statements before the first newmtl have nothing to apply to, so ignore them
+    let material = match materials.last_mut() {
+        Some(material) => material,
+        None => return Ok(()),
+    };
+
+    match line_type {
+        "Ka" => material.ambient = parse_color("Ka", words)?,
+        "Kd" => material.diffuse = parse_color("Kd", words)?,
+        "Ks" => material.specular = parse_color("Ks", words)?,
+        "Ns" => material.shininess = parse_floats("Ns", 1, words)?[0],
+        "d" => material.dissolve = parse_floats("d", 1, words)?[0],
+        "Tr" => material.dissolve = 1.0 - parse_floats("Tr", 1, words)?[0],
+        "map_Kd" => {
+            // options such as `-s 1 1 1` can come before the filename, which is always last
+            if let Some(path) = words.last() {
+                let path = dir.join(path);
+                material.diffuse_texture = match Texture::read(&path) {
+                    Ok(texture) => Some(Arc::new(texture)),
+                    Err(err) => {
+                        eprintln!("warning: skipping texture: {}", err);
+                        None
+                    },
+                };
+                material.diffuse_map = Some(path);
+            }
+        },
+        "filter" => {
+            let name = words.next().unwrap_or_default();
+            material.texture_filter = Some(Filter::from_name(name).ok_or_else(|| LoadErrorKind::Parse(name.to_string()))?);
+        },
+        _ => {},
+    }
+    Ok(())
+}
+
+/// Parse an RGB color, where a single value is used for all three components
+fn parse_color<'a>(element: &'static str, words: impl Iterator<Item = &'a str>) -> Result<Vector3<f64>, LoadErrorKind> {
+    let values = parse_floats(element, 1, words)?;
+    if values.len() < 3 {
+        Ok(Vector3::new(values[0], values[0], values[0]))
+    } else {
+        Ok(Vector3::new(values[0], values[1], values[2]))
+    }
+}
@@ -0,0 +1,139 @@
+use nalgebra::{Matrix4, Point3, Unit, UnitQuaternion, Vector3};
+
+#[rustfmt::skip]
+pub fn scale(scale: f64) -> Matrix4<f64> {
+    Matrix4::new(
+        scale,   0.0,   0.0, 0.0,
+          0.0, scale,   0.0, 0.0,
+          0.0,   0.0, scale, 0.0,
+          0.0,   0.0,   0.0, 1.0,
+    )
+}
+
+#[rustfmt::skip]
+pub fn translate(point: Point3<f64>) -> Matrix4<f64> {
+    Matrix4::new(
+          1.0,   0.0,   0.0,   point[0],
+          0.0,   1.0,   0.0,   point[1],
+          0.0,   0.0,   1.0,   point[2],
+          0.0,   0.0,   0.0, 1.0,
+    )
+}
+
+/// The orientation given by rotations in degrees around the x, y and z axes.  For a camera these are
+/// the pitch (positive looks down), the yaw (positive turns right), and the roll around the
+/// direction it's looking
+pub fn quaternion_from_euler(angles: Vector3<f64>) -> UnitQuaternion<f64> {
+    let rotation = |axis, angle: f64| UnitQuaternion::from_axis_angle(&axis, angle.to_radians());
+    rotation(Vector3::y_axis(), -angles.y) * rotation(Vector3::x_axis(), angles.x) * rotation(Vector3::z_axis(), angles.z)
+}
+
+/// The rotations in degrees around each axis that give the orientation, using the same convention as
+/// `quaternion_from_euler`, with the pitch between -90 and 90 degrees
+pub fn euler_from_quaternion(orientation: &UnitQuaternion<f64>) -> Vector3<f64> {
+    let forward = orientation * Vector3::z();
+    let pitch = (-forward.y).clamp(-1.0, 1.0).asin();
+    let yaw = (-forward.x).atan2(forward.z);
+    let rotation = |axis, angle: f64| UnitQuaternion::from_axis_angle(&axis, angle);
+    let roll = (rotation(Vector3::y_axis(), -yaw) * rotation(Vector3::x_axis(), pitch)).inverse() * orientation;
+    let left = roll * Vector3::x();
+    Vector3::new(pitch.to_degrees(), yaw.to_degrees(), left.y.atan2(left.x).to_degrees())
+}
+
+/// The orientation given by a rotation in degrees around an axis, counter-clockwise when looking
+/// back along the axis.  A zero axis gives no rotation
+pub fn quaternion_from_axis_angle(axis: Vector3<f64>, angle: f64) -> UnitQuaternion<f64> {
+    match Unit::try_new(axis, f64::EPSILON) {
+        Some(axis) => UnitQuaternion::from_axis_angle(&axis, angle.to_radians()),
+        None => UnitQuaternion::identity(),
+    }
+}
+
+/// The axis and angle in degrees of the rotation that gives the orientation, with the x axis for no rotation
+pub fn axis_angle_from_quaternion(orientation: &UnitQuaternion<f64>) -> (Vector3<f64>, f64) {
+    match orientation.axis_angle() {
+        Some((axis, angle)) => (axis.into_inner(), angle.to_degrees()),
+        None => (Vector3::x(), 0.0),
+    }
+}
+
+/// A perspective projection for a camera looking down the +z axis, with +y up and so +x to the left.
+/// Points between the near and far planes end up with a positive w, and a depth from -1 to 1
+#[rustfmt::skip]
+pub fn perspective_transform_fov(fov: f64, aspect: f64, n: f64, f: f64) -> Matrix4<f64> {
+    let e = 1.0 / (fov / 2.0).tan();
+    Matrix4::new(
+     -e / aspect,   0.0,                 0.0,                       0.0,
+             0.0,   e,                   0.0,                       0.0,
+             0.0,   0.0,   (f + n) / (f - n),   (2.0 * f * n) / (n - f),
+             0.0,   0.0,                 1.0,                       0.0,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn euler_angles_round_trip() {
+        for pitch in (-85..=85).step_by(17) {
+            for yaw in (-170..=170).step_by(34) {
+                for roll in (-170..=170).step_by(34) {
+                    let angles = Vector3::new(pitch as f64, yaw as f64, roll as f64);
+                    let round_trip = euler_from_quaternion(&quaternion_from_euler(angles));
+                    assert!(
+                        (round_trip - angles).norm() < 1e-9,
+                        "{:?} came back as {:?}",
+                        angles,
+                        round_trip
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn orientation_round_trips_through_euler_angles() {
+        // pitching over the top gives different angles, but they still describe the same orientation
+        for angles in [
+            Vector3::new(120.0, 30.0, 10.0),
+            Vector3::new(-100.0, -160.0, 45.0),
+            Vector3::new(90.0, 0.0, 0.0),
+        ] {
+            let orientation = quaternion_from_euler(angles);
+            let round_trip = quaternion_from_euler(euler_from_quaternion(&orientation));
+            assert!(orientation.angle_to(&round_trip) < 1e-6, "{:?} changed orientation", angles);
+        }
+    }
+
+    #[test]
+    fn euler_angles_follow_the_camera_conventions() {
+        // positive pitch looks down and positive yaw turns right, which is towards -x
+        let down = quaternion_from_euler(Vector3::new(90.0, 0.0, 0.0)) * Vector3::z();
+        assert!((down - Vector3::new(0.0, -1.0, 0.0)).norm() < 1e-9);
+        let right = quaternion_from_euler(Vector3::new(0.0, 90.0, 0.0)) * Vector3::z();
+        assert!((right - Vector3::new(-1.0, 0.0, 0.0)).norm() < 1e-9);
+    }
+
+    #[test]
+    fn projection_looks_down_positive_z() {
+        let (near, far) = (1.0, 100.0);
+        let projection = perspective_transform_fov(std::f64::consts::FRAC_PI_2, 1.0, near, far);
+        let project = |x: f64, y: f64, z: f64| projection * nalgebra::Vector4::new(x, y, z, 1.0);
+
+        // points in front of the camera have a positive w, and a depth from -1 at the near plane to 1
+        // at the far plane
+        for z in [near, 10.0, far] {
+            assert!(project(0.0, 0.0, z).w > 0.0);
+        }
+        let depth = |z: f64| project(0.0, 0.0, z).z / project(0.0, 0.0, z).w;
+        assert!((depth(near) + 1.0).abs() < 1e-9);
+        assert!((depth(far) - 1.0).abs() < 1e-9);
+        assert!(depth(near) < depth(10.0) && depth(10.0) < depth(far));
+
+        // +x is on the left of the screen and +y is up
+        let corner = project(1.0, 1.0, 10.0);
+        assert!(corner.x / corner.w < 0.0);
+        assert!(corner.y / corner.w > 0.0);
+    }
+}
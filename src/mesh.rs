@@ -0,0 +1,677 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use nalgebra::{Matrix4, Point2, Point3, Vector3, Vector4};
+
+use crate::camera::Camera;
+use crate::error::{LoadError, LoadErrorKind};
+use crate::material::Material;
+use crate::triangulate::{polygon_normal, triangulate_polygon};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FaceVertex {
+    pub point: usize,
+    pub texcoord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+impl FaceVertex {
+    /// Parse a face vertex in any of the forms `v`, `v/vt`, `v//vn` or `v/vt/vn`, converting the
+    /// 1-based (or negative relative) indices into 0-based indices using the number of each element read so far
+    pub fn parse(word: &str, num_points: usize, num_texcoords: usize, num_normals: usize) -> Result<FaceVertex, LoadErrorKind> {
+        let mut parts = word.split('/');
+        let point = resolve_index(parts.next().unwrap_or(""), num_points)?.ok_or_else(|| LoadErrorKind::Parse(word.to_string()))?;
+        let texcoord = parts
+            .next()
+            .map(|part| resolve_index(part, num_texcoords))
+            .transpose()?
+            .flatten();
+        let normal = parts
+            .next()
+            .map(|part| resolve_index(part, num_normals))
+            .transpose()?
+            .flatten();
+
+        Ok(FaceVertex {
+            point,
+            texcoord,
+            normal,
+        })
+    }
+}
+
+/// Convert an OBJ index, which either counts from 1 or counts backwards from the most recent element when
+/// negative, into a 0-based index.  An empty index (eg. the missing texcoord in `1//3`) returns None
+fn resolve_index(word: &str, count: usize) -> Result<Option<usize>, LoadErrorKind> {
+    if word.is_empty() {
+        return Ok(None);
+    }
+
+    let index = parse_value::<isize>(word)?;
+    let resolved = if index < 0 { count as isize + index } else { index - 1 };
+    if index == 0 || resolved < 0 || resolved >= count as isize {
+        return Err(LoadErrorKind::IndexOutOfRange {
+            index,
+            count,
+        });
+    }
+    Ok(Some(resolved as usize))
+}
+
+pub(crate) fn parse_value<T: FromStr>(word: &str) -> Result<T, LoadErrorKind> {
+    word.parse::<T>().map_err(|_| LoadErrorKind::Parse(word.to_string()))
+}
+
+/// Parse all the remaining words on a line as floats, making sure there are at least `expected` of them
+pub(crate) fn parse_floats<'a>(
+    element: &'static str,
+    expected: usize,
+    words: impl Iterator<Item = &'a str>,
+) -> Result<Vec<f64>, LoadErrorKind> {
+    let values = words.map(parse_value::<f64>).collect::<Result<Vec<f64>, LoadErrorKind>>()?;
+    if values.len() < expected {
+        return Err(LoadErrorKind::WrongArity {
+            element,
+            expected,
+            found: values.len(),
+        });
+    }
+    Ok(values)
+}
+
+#[derive(Clone, Debug)]
+pub struct Face {
+    pub vertices: Vec<FaceVertex>,
+    /// Index into the object's materials, if a known material was in use when the face was defined
+    pub material: Option<usize>,
+    /// The smoothing group id, or None if smoothing was off (`s off` or `s 0`)
+    pub smoothing_group: Option<u32>,
+}
+
+/// A triangle from a triangulated face, which keeps the index of the face it came from
+#[derive(Copy, Clone, Debug)]
+pub struct Triangle {
+    pub face: usize,
+    pub vertices: [FaceVertex; 3],
+}
+
+/// A named group of faces (from a `g` statement), which can be shown or hidden as a unit
+#[derive(Clone, Debug)]
+pub struct Group {
+    pub name: String,
+    /// The ranges of face indices in the group, since the same group can be reopened later in the file
+    pub ranges: Vec<Range<usize>>,
+    pub visible: bool,
+}
+
+impl Group {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ranges: vec![],
+            visible: true,
+        }
+    }
+
+    /// The indices of all faces in this group
+    pub fn faces(&self) -> impl Iterator<Item = usize> + '_ {
+        self.ranges.iter().flat_map(|range| range.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Object {
+    pub points: Vec<Point3<f64>>,
+    pub texcoords: Vec<Point2<f64>>,
+    pub normals: Vec<Vector3<f64>>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Material>,
+    pub groups: Vec<Group>,
+}
+
+/// The parser state that applies to each face as it's read
+struct ReadState {
+    dir: PathBuf,
+    material: Option<usize>,
+    smoothing_group: Option<u32>,
+    /// The names of the groups the current faces belong to, and the index of the first of those faces
+    group_names: Vec<String>,
+    group_start: usize,
+}
+
+impl Object {
+    pub fn read(filename: impl AsRef<Path>) -> Result<Object, LoadError> {
+        let filename = filename.as_ref();
+        let file = File::open(filename).map_err(|err| LoadError::new(filename, None, err.into()))?;
+        let reader = BufReader::new(file);
+
+        let mut object = Object {
+            points: vec![],
+            texcoords: vec![],
+            normals: vec![],
+            faces: vec![],
+            materials: vec![],
+            groups: vec![],
+        };
+
+        let mut state = ReadState {
+            dir: filename.parent().unwrap_or(Path::new("")).to_path_buf(),
+            material: None,
+            smoothing_group: None,
+            group_names: vec!["default".to_string()],
+            group_start: 0,
+        };
+
+        for (i, line) in reader.lines().enumerate() {
+            object
+                .read_line(&mut state, line)
+                .map_err(|kind| LoadError::new(filename, Some(i + 1), kind))?;
+        }
+        object.close_groups(&mut state);
+
+        Ok(object)
+    }
+
+    fn read_line(&mut self, state: &mut ReadState, line: Result<String, io::Error>) -> Result<(), LoadErrorKind> {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        if let Some(line_type) = words.next() {
+            match line_type {
+                "v" => {
+                    let coords = parse_floats("v", 3, words)?;
+                    self.points.push(Point3::new(coords[0], coords[1], coords[2]));
+                },
+                "vt" => {
+                    // the v and w components are optional, and w is not used
+                    let coords = parse_floats("vt", 1, words)?;
+                    self.texcoords
+                        .push(Point2::new(coords[0], coords.get(1).copied().unwrap_or(0.0)));
+                },
+                "vn" => {
+                    let coords = parse_floats("vn", 3, words)?;
+                    self.normals.push(Vector3::new(coords[0], coords[1], coords[2]));
+                },
+                "f" => {
+                    let vertices = words
+                        .map(|w| FaceVertex::parse(w, self.points.len(), self.texcoords.len(), self.normals.len()))
+                        .collect::<Result<Vec<FaceVertex>, LoadErrorKind>>()?;
+                    if vertices.len() < 3 {
+                        return Err(LoadErrorKind::WrongArity {
+                            element: "f",
+                            expected: 3,
+                            found: vertices.len(),
+                        });
+                    }
+                    self.faces.push(Face {
+                        vertices,
+                        material: state.material,
+                        smoothing_group: state.smoothing_group,
+                    });
+                },
+                "mtllib" => {
+                    for name in words {
+                        self.read_material_library(&state.dir.join(name));
+                    }
+                },
+                "usemtl" => {
+                    let name = words.collect::<Vec<&str>>().join(" ");
+                    state.material = self.materials.iter().position(|material| material.name == name);
+                },
+                "g" => {
+                    self.close_groups(state);
+                    state.group_names = words.map(|name| name.to_string()).collect();
+                    if state.group_names.is_empty() {
+                        state.group_names.push("default".to_string());
+                    }
+                },
+                "s" => {
+                    state.smoothing_group = match words.next() {
+                        None | Some("off") => None,
+                        Some(word) => Some(parse_value::<u32>(word)?).filter(|id| *id != 0),
+                    };
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the faces read since the last `g` statement to each of the groups it named
+    fn close_groups(&mut self, state: &mut ReadState) {
+        let range = state.group_start..self.faces.len();
+        state.group_start = self.faces.len();
+        if range.is_empty() {
+            return;
+        }
+
+        for name in &state.group_names {
+            let index = match self.groups.iter().position(|group| &group.name == name) {
+                Some(index) => index,
+                None => {
+                    self.groups.push(Group::new(name));
+                    self.groups.len() - 1
+                },
+            };
+            self.groups[index].ranges.push(range.clone());
+        }
+    }
+
+    /// Show or hide all the faces in the named group, returning false if there is no such group
+    pub fn set_group_visible(&mut self, name: &str, visible: bool) -> bool {
+        match self.groups.iter_mut().find(|group| group.name == name) {
+            Some(group) => {
+                group.visible = visible;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Split every face into triangles, using ear clipping so that concave faces are handled correctly
+    pub fn triangulate(&self) -> Vec<Triangle> {
+        let mut triangles = vec![];
+        for (i, face) in self.faces.iter().enumerate() {
+            let points: Vec<Point3<f64>> = face.vertices.iter().map(|vertex| self.points[vertex.point]).collect();
+            triangles.extend(triangulate_polygon(&points).into_iter().map(|[a, b, c]| Triangle {
+                face: i,
+                vertices: [face.vertices[a], face.vertices[b], face.vertices[c]],
+            }));
+        }
+        triangles
+    }
+
+    /// The unit normal of each face, pointing the way the right-hand rule gives for its vertices, or
+    /// zero for faces with no area
+    pub fn face_normals(&self) -> Vec<Vector3<f64>> {
+        self.faces
+            .iter()
+            .map(|face| {
+                let points: Vec<Point3<f64>> = face.vertices.iter().map(|vertex| self.points[vertex.point]).collect();
+                polygon_normal(&points).try_normalize(0.0).unwrap_or_else(Vector3::zeros)
+            })
+            .collect()
+    }
+
+    /// The normal at each corner of each triangle, for smooth shading.  Normals given in the file are
+    /// used as they are, and otherwise the normals of all the faces in the same smoothing group that
+    /// share the point are averaged, weighted by the angle of each face's corner there.  Faces that
+    /// aren't in a smoothing group just use their own normal
+    pub fn vertex_normals(&self, triangles: &[Triangle]) -> Vec<[Vector3<f64>; 3]> {
+        let face_normals = self.face_normals();
+
+        let mut smoothed: HashMap<(usize, u32), Vector3<f64>> = HashMap::new();
+        for (face, normal) in self.faces.iter().zip(&face_normals) {
+            let group = match face.smoothing_group {
+                Some(group) => group,
+                None => continue,
+            };
+
+            let count = face.vertices.len();
+            for (i, vertex) in face.vertices.iter().enumerate() {
+                let point = self.points[vertex.point];
+                let previous = self.points[face.vertices[(i + count - 1) % count].point] - point;
+                let next = self.points[face.vertices[(i + 1) % count].point] - point;
+                if previous.norm() == 0.0 || next.norm() == 0.0 {
+                    continue;
+                }
+                *smoothed.entry((vertex.point, group)).or_insert_with(Vector3::zeros) += normal * previous.angle(&next);
+            }
+        }
+
+        triangles
+            .iter()
+            .map(|triangle| {
+                let face_normal = face_normals[triangle.face];
+                let group = self.faces[triangle.face].smoothing_group;
+                triangle.vertices.map(|vertex| {
+                    let normal = match (vertex.normal, group) {
+                        (Some(normal), _) => self.normals[normal],
+                        (None, Some(group)) => smoothed.get(&(vertex.point, group)).copied().unwrap_or(face_normal),
+                        (None, None) => face_normal,
+                    };
+                    normal.try_normalize(0.0).unwrap_or(face_normal)
+                })
+            })
+            .collect()
+    }
+
+    /// The diffuse color of the face's material, if it has one
+    pub fn face_color(&self, face: usize) -> Option<[f32; 4]> {
+        self.face_material(face).map(|material| material.diffuse_color())
+    }
+
+    pub fn face_material(&self, face: usize) -> Option<&Material> {
+        self.faces[face].material.map(|material| &self.materials[material])
+    }
+
+    /// Whether each face should be drawn, where a face is hidden if any group it belongs to is hidden
+    pub fn face_visibility(&self) -> Vec<bool> {
+        let mut visible = vec![true; self.faces.len()];
+        for group in self.groups.iter().filter(|group| !group.visible) {
+            for i in group.faces() {
+                visible[i] = false;
+            }
+        }
+        visible
+    }
+
+    /// The distinct smoothing group ids used by any face, in ascending order
+    pub fn smoothing_groups(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.faces.iter().filter_map(|face| face.smoothing_group).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// The faces that aren't hidden
+    pub fn visible_faces(&self) -> impl Iterator<Item = &Face> {
+        self.faces
+            .iter()
+            .zip(self.face_visibility())
+            .filter_map(|(face, visible)| visible.then_some(face))
+    }
+
+    /// The number of different points used by faces that aren't hidden, leaving out any that no
+    /// such face refers to
+    pub fn used_point_count(&self) -> usize {
+        let points: HashSet<usize> = self
+            .visible_faces()
+            .flat_map(|face| face.vertices.iter().map(|vertex| vertex.point))
+            .collect();
+        points.len()
+    }
+
+    /// The number of different edges between points on faces that aren't hidden, where an edge
+    /// shared by several faces only counts once
+    pub fn edge_count(&self) -> usize {
+        let mut edges = HashSet::new();
+        for face in self.visible_faces() {
+            for (i, from) in face.vertices.iter().enumerate() {
+                let to = face.vertices[(i + 1) % face.vertices.len()];
+                if from.point != to.point {
+                    edges.insert((from.point.min(to.point), from.point.max(to.point)));
+                }
+            }
+        }
+        edges.len()
+    }
+
+    /// The corners of the smallest box containing every point used by a face, or None if there are no faces
+    pub fn bounds(&self) -> Option<(Point3<f64>, Point3<f64>)> {
+        self.bounds_of(0..self.faces.len())
+    }
+
+    /// The corners of the smallest box containing the faces in the named group, or None if there is no such group
+    pub fn group_bounds(&self, name: &str) -> Option<(Point3<f64>, Point3<f64>)> {
+        let group = self.groups.iter().find(|group| group.name == name)?;
+        self.bounds_of(group.faces())
+    }
+
+    fn bounds_of(&self, faces: impl Iterator<Item = usize>) -> Option<(Point3<f64>, Point3<f64>)> {
+        let mut points = faces.flat_map(|i| self.faces[i].vertices.iter().map(|vertex| self.points[vertex.point]));
+        let first = points.next()?;
+        Some(points.fold((first, first), |(min, max), point| (min.inf(&point), max.sup(&point))))
+    }
+
+    /// Move the faces in any of the named groups into a new object, so that they can be placed separately.
+    /// Each object keeps only the points, texture coordinates and normals its own faces use, both keep
+    /// all of the materials, and the groups are split between them along with their faces
+    pub fn split_groups(&mut self, names: &[&str]) -> Object {
+        let mut moved = vec![false; self.faces.len()];
+        for group in self.groups.iter().filter(|group| names.contains(&group.name.as_str())) {
+            for i in group.faces() {
+                moved[i] = true;
+            }
+        }
+
+        // where each face ends up in whichever object it belongs to
+        let mut new_index = vec![0; self.faces.len()];
+        let (mut kept, mut split) = (vec![], vec![]);
+        for (i, face) in std::mem::take(&mut self.faces).into_iter().enumerate() {
+            let faces = if moved[i] { &mut split } else { &mut kept };
+            new_index[i] = faces.len();
+            faces.push(face);
+        }
+        self.faces = kept;
+
+        let mut split_groups = vec![];
+        for group in std::mem::take(&mut self.groups) {
+            let mut kept_group = Group {
+                ranges: vec![],
+                ..group.clone()
+            };
+            let mut split_group = Group {
+                ranges: vec![],
+                ..group.clone()
+            };
+            for i in group.faces() {
+                let target = if moved[i] { &mut split_group } else { &mut kept_group };
+                match target.ranges.last_mut() {
+                    Some(range) if range.end == new_index[i] => range.end += 1,
+                    _ => target.ranges.push(new_index[i]..new_index[i] + 1),
+                }
+            }
+
+            // groups without any faces stay with the original object
+            if !kept_group.ranges.is_empty() || split_group.ranges.is_empty() {
+                self.groups.push(kept_group);
+            }
+            if !split_group.ranges.is_empty() {
+                split_groups.push(split_group);
+            }
+        }
+
+        let mut split = Object {
+            points: self.points.clone(),
+            texcoords: self.texcoords.clone(),
+            normals: self.normals.clone(),
+            faces: split,
+            materials: self.materials.clone(),
+            groups: split_groups,
+        };
+        split.remove_unused_vertices();
+        self.remove_unused_vertices();
+        split
+    }
+
+    /// Drop the points, texture coordinates and normals that no face uses, keeping the rest in order
+    fn remove_unused_vertices(&mut self) {
+        let vertices = || self.faces.iter().flat_map(|face| face.vertices.iter());
+        let points = compact(&mut self.points, vertices().map(|vertex| vertex.point));
+        let texcoords = compact(&mut self.texcoords, vertices().filter_map(|vertex| vertex.texcoord));
+        let normals = compact(&mut self.normals, vertices().filter_map(|vertex| vertex.normal));
+
+        for vertex in self.faces.iter_mut().flat_map(|face| face.vertices.iter_mut()) {
+            vertex.point = points[vertex.point];
+            vertex.texcoord = vertex.texcoord.map(|texcoord| texcoords[texcoord]);
+            vertex.normal = vertex.normal.map(|normal| normals[normal]);
+        }
+    }
+
+    /// Add the materials from the given library.  A missing or broken library isn't fatal, since the
+    /// geometry is still usable, so any errors are only reported
+    fn read_material_library(&mut self, path: &Path) {
+        match Material::read_library(path) {
+            Ok(materials) => self.materials.extend(materials),
+            Err(err) => eprintln!("warning: skipping material library: {}", err),
+        }
+    }
+
+    /// Transform every point into clip space, for an object placed in the world with the given
+    /// transform and seen from the camera
+    pub fn project(&self, world_from_object: &Matrix4<f64>, camera: &Camera, window_size: [f64; 2]) -> Vec<Vector4<f64>> {
+        let perspective_from_camera = camera.projection.matrix(window_size[0] / window_size[1]);
+        let camera_from_world = camera.view_matrix();
+
+        self.points
+            .iter()
+            .map(|point| point.to_homogeneous())
+            .map(|point| perspective_from_camera * camera_from_world * world_from_object * point)
+            .collect()
+    }
+}
+
+/// Keep only the elements at the used indices, in their original order, returning the new index of
+/// each element that was kept
+fn compact<T: Copy>(elements: &mut Vec<T>, used: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut is_used = vec![false; elements.len()];
+    for i in used {
+        is_used[i] = true;
+    }
+
+    let mut new_index = vec![0; elements.len()];
+    let mut kept = 0;
+    for i in 0..elements.len() {
+        if is_used[i] {
+            new_index[i] = kept;
+            elements[kept] = elements[i];
+            kept += 1;
+        }
+    }
+    elements.truncate(kept);
+    new_index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positive_indices_count_from_one() {
+        assert_eq!(resolve_index("1", 3).unwrap(), Some(0));
+        assert_eq!(resolve_index("3", 3).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_last_element() {
+        assert_eq!(resolve_index("-1", 3).unwrap(), Some(2));
+        assert_eq!(resolve_index("-3", 3).unwrap(), Some(0));
+    }
+
+    #[test]
+    fn empty_index_is_missing() {
+        assert_eq!(resolve_index("", 3).unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_indices() {
+        for (word, index) in [("0", 0), ("4", 4), ("-4", -4)] {
+            match resolve_index(word, 3) {
+                Err(LoadErrorKind::IndexOutOfRange {
+                    index: found,
+                    count: 3,
+                }) => assert_eq!(found, index),
+                result => panic!("expected {} to be out of range, got {:?}", word, result),
+            }
+        }
+        assert!(matches!(resolve_index("1", 0), Err(LoadErrorKind::IndexOutOfRange { .. })));
+        assert!(matches!(resolve_index("x", 3), Err(LoadErrorKind::Parse(_))));
+    }
+
+    #[test]
+    fn face_vertex_forms() {
+        let parse = |word| FaceVertex::parse(word, 5, 4, 3).unwrap();
+        let vertex = |point, texcoord, normal| FaceVertex {
+            point,
+            texcoord,
+            normal,
+        };
+        assert_eq!(parse("2"), vertex(1, None, None));
+        assert_eq!(parse("2/4"), vertex(1, Some(3), None));
+        assert_eq!(parse("2//-1"), vertex(1, None, Some(2)));
+        assert_eq!(parse("-1/1/3"), vertex(4, Some(0), Some(2)));
+        assert!(FaceVertex::parse("/1", 5, 4, 3).is_err());
+    }
+
+    /// An object made of triangles, each given as its corners and smoothing group
+    fn triangles(points: &[[f64; 3]], normals: &[[f64; 3]], faces: &[([FaceVertex; 3], Option<u32>)]) -> Object {
+        Object {
+            points: points.iter().map(|&point| point.into()).collect(),
+            texcoords: vec![],
+            normals: normals.iter().map(|&normal| normal.into()).collect(),
+            faces: faces
+                .iter()
+                .map(|(vertices, smoothing_group)| Face {
+                    vertices: vertices.to_vec(),
+                    material: None,
+                    smoothing_group: *smoothing_group,
+                })
+                .collect(),
+            materials: vec![],
+            groups: vec![],
+        }
+    }
+
+    fn vertex(point: usize) -> FaceVertex {
+        FaceVertex {
+            point,
+            texcoord: None,
+            normal: None,
+        }
+    }
+
+    /// Two triangles folded at a right angle along the edge from point 0 to point 1, one facing +z
+    /// and the other +y
+    const FOLD: [[f64; 3]; 4] = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+
+    #[test]
+    fn normals_from_the_file_are_used_as_they_are() {
+        let mut faces = [[vertex(0), vertex(1), vertex(2)], [vertex(0), vertex(3), vertex(1)]];
+        faces[0][1].normal = Some(0);
+        let object = triangles(&FOLD, &[[3.0, 0.0, 0.0]], &[(faces[0], Some(1)), (faces[1], Some(1))]);
+        let normals = object.vertex_normals(&object.triangulate());
+        assert_eq!(normals[0][1], Vector3::x());
+    }
+
+    #[test]
+    fn normals_are_only_smoothed_within_a_smoothing_group() {
+        let faces = [[vertex(0), vertex(1), vertex(2)], [vertex(0), vertex(3), vertex(1)]];
+        let shared = Vector3::new(0.0, 1.0, 1.0).normalize();
+        for (groups, smoothed) in [([Some(1), Some(1)], true), ([Some(1), Some(2)], false), ([None, None], false)] {
+            let object = triangles(&FOLD, &[], &[(faces[0], groups[0]), (faces[1], groups[1])]);
+            let face_normals = object.face_normals();
+            assert_eq!(face_normals, [Vector3::z(), Vector3::y()]);
+
+            let triangles = object.triangulate();
+            for (triangle, normals) in triangles.iter().zip(object.vertex_normals(&triangles)) {
+                for (vertex, normal) in triangle.vertices.iter().zip(normals) {
+                    let expected = if smoothed && vertex.point < 2 {
+                        shared
+                    } else {
+                        face_normals[triangle.face]
+                    };
+                    assert!(
+                        (normal - expected).norm() < 1e-9,
+                        "{:?} in groups {:?} has normal {:?}",
+                        vertex,
+                        groups,
+                        normal
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hidden_groups_are_not_counted() {
+        let faces = [[vertex(0), vertex(1), vertex(2)], [vertex(0), vertex(3), vertex(1)]];
+        let mut object = triangles(&FOLD, &[], &[(faces[0], None), (faces[1], None)]);
+        object.groups = vec![Group::new("flat"), Group::new("up")];
+        object.groups[0].ranges.push(0..1);
+        object.groups[1].ranges.push(1..2);
+        assert_eq!(
+            (object.used_point_count(), object.edge_count(), object.visible_faces().count()),
+            (4, 5, 2)
+        );
+
+        object.set_group_visible("up", false);
+        assert_eq!(
+            (object.used_point_count(), object.edge_count(), object.visible_faces().count()),
+            (3, 3, 1)
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use rather3d::camera::Projection;
+use rather3d::render::{RenderMode, Shading};
+use rather3d::texture::Filter;
+
+use crate::{SIZE_X, SIZE_Y};
+
+pub const USAGE: &str = "\
+Usage: rather3d [OPTIONS] [MODEL]...
+
+Arguments:
+  [MODEL]...              Wavefront OBJ files to view, placed side by side
+
+Options:
+      --scene <FILE>      the scene file to view, which the models are added to (default: data/cessna.scene
+                          if there are no models)
+  -s, --size <WxH>        the window or image size in pixels (default: 1920x1080)
+      --fov <DEGREES>     the vertical field of view (default: 45)
+      --near <DISTANCE>   the distance to the near clipping plane (default: 1)
+      --far <DISTANCE>    the distance to the far clipping plane (default: 10000)
+  -m, --mode <MODE>       the render mode to start in, either wireframe or solid (default: wireframe)
+      --shading <SHADING> how to light solid surfaces, one of unlit, flat, gouraud, phong
+                          or blinn-phong (default: flat)
+      --filter <FILTER>   how to sample textures whose material doesn't say, one of nearest,
+                          bilinear or trilinear (default: trilinear)
+  -c, --cull              hide faces pointing away from the camera
+  -o, --output <FILE>     render a single frame to a PNG file instead of opening a window
+  -f, --font <FONT>       the font file, or the name of a font file in the system font directories,
+                          to use for the overlay text (default: search for a common font)
+      --sensitivity <DEGREES>
+                          how far the camera turns for each pixel the mouse moves (default: 0.2)
+      --invert-y          look down when the mouse moves up, and up when it moves down
+  -b, --bindings <FILE>   the file of key and mouse bindings to use instead of the defaults (default:
+                          ~/.config/rather3d/bindings if it exists)
+  -h, --help              print this help message
+
+The --fov, --near and --far options apply to the scene's own cameras too, in place of their settings";
+
+#[derive(Debug)]
+pub enum OptionsError {
+    /// The help message was asked for, which isn't an error but means nothing else should be done
+    Help,
+    MissingValue(String),
+    InvalidValue(String, String),
+    UnknownOption(String),
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionsError::Help => write!(f, "{}", USAGE),
+            OptionsError::MissingValue(option) => write!(f, "missing value for {}", option),
+            OptionsError::InvalidValue(option, value) => write!(f, "invalid value {:?} for {}", value, option),
+            OptionsError::UnknownOption(option) => write!(f, "unknown option {}", option),
+        }
+    }
+}
+
+/// The projection settings given on the command line, each of which replaces the setting of
+/// whichever camera is used, including those loaded from the scene
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ProjectionOptions {
+    pub fov: Option<f64>,
+    pub near: Option<f64>,
+    pub far: Option<f64>,
+}
+
+impl ProjectionOptions {
+    /// The projection with any settings given on the command line in place of its own, as long as
+    /// the far plane is still beyond the near one
+    pub fn apply(&self, projection: &Projection) -> Result<Projection, OptionsError> {
+        let projection = Projection {
+            fov: self.fov.unwrap_or(projection.fov),
+            near: self.near.unwrap_or(projection.near),
+            far: self.far.unwrap_or(projection.far),
+        };
+        if projection.far <= projection.near {
+            let (option, value) = match self.far {
+                Some(far) => ("--far", far),
+                None => ("--near", projection.near),
+            };
+            return Err(OptionsError::InvalidValue(option.to_string(), value.to_string()));
+        }
+        Ok(projection)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub models: Vec<String>,
+    pub scene: Option<String>,
+    pub window_size: [f64; 2],
+    pub projection: ProjectionOptions,
+    pub mode: RenderMode,
+    pub shading: Shading,
+    pub texture_filter: Filter,
+    pub cull_backfaces: bool,
+    pub output: Option<String>,
+    pub font: Option<String>,
+    pub bindings: Option<String>,
+    /// Degrees to turn for each pixel the mouse moves
+    pub sensitivity: f64,
+    pub invert_y: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            models: vec![],
+            scene: None,
+            window_size: [SIZE_X, SIZE_Y],
+            projection: ProjectionOptions::default(),
+            mode: RenderMode::Wireframe,
+            shading: Shading::Flat,
+            texture_filter: Filter::Trilinear,
+            cull_backfaces: false,
+            output: None,
+            font: None,
+            bindings: None,
+            sensitivity: 0.2,
+            invert_y: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parse the command line arguments, not including the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Options, OptionsError> {
+        let mut options = Options::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| OptionsError::MissingValue(arg.clone()));
+            match arg.as_str() {
+                "-h" | "--help" => return Err(OptionsError::Help),
+                "-s" | "--size" => options.window_size = parse_size(&arg, &value()?)?,
+                "--fov" => options.projection.fov = Some(parse_range(&arg, &value()?, 0.0, 180.0)?),
+                "--near" => options.projection.near = Some(parse_range(&arg, &value()?, 0.0, f64::INFINITY)?),
+                "--far" => options.projection.far = Some(parse_range(&arg, &value()?, 0.0, f64::INFINITY)?),
+                "-m" | "--mode" => {
+                    let value = value()?;
+                    options.mode = match value.as_str() {
+                        "wireframe" => RenderMode::Wireframe,
+                        "solid" => RenderMode::Solid,
+                        _ => return Err(OptionsError::InvalidValue(arg, value)),
+                    };
+                },
+                "--shading" => {
+                    let value = value()?;
+                    options.shading = match value.as_str() {
+                        "unlit" => Shading::Unlit,
+                        "flat" => Shading::Flat,
+                        "gouraud" => Shading::Gouraud,
+                        "phong" => Shading::Phong,
+                        "blinn-phong" => Shading::BlinnPhong,
+                        _ => return Err(OptionsError::InvalidValue(arg, value)),
+                    };
+                },
+                "--filter" => {
+                    let value = value()?;
+                    options.texture_filter = match Filter::from_name(&value) {
+                        Some(filter) => filter,
+                        None => return Err(OptionsError::InvalidValue(arg, value)),
+                    };
+                },
+                "-c" | "--cull" => options.cull_backfaces = true,
+                "--scene" => options.scene = Some(value()?),
+                "-o" | "--output" => options.output = Some(value()?),
+                "-f" | "--font" => options.font = Some(value()?),
+                "-b" | "--bindings" => options.bindings = Some(value()?),
+                "--sensitivity" => options.sensitivity = parse_range(&arg, &value()?, 0.0, f64::INFINITY)?,
+                "--invert-y" => options.invert_y = true,
+                _ if arg.starts_with('-') => return Err(OptionsError::UnknownOption(arg)),
+                _ => options.models.push(arg),
+            }
+        }
+
+        // the near and far planes are checked against each camera as it's used, but when both are
+        // given they can be checked now
+        if let (Some(near), Some(far)) = (options.projection.near, options.projection.far) {
+            if far <= near {
+                return Err(OptionsError::InvalidValue("--far".to_string(), far.to_string()));
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Parse a size given as `WIDTHxHEIGHT`
+fn parse_size(option: &str, value: &str) -> Result<[f64; 2], OptionsError> {
+    let invalid = || OptionsError::InvalidValue(option.to_string(), value.to_string());
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    let width = width.parse::<u32>().map_err(|_| invalid())?;
+    let height = height.parse::<u32>().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok([width as f64, height as f64])
+}
+
+/// Parse a number which must be strictly between the given limits
+fn parse_range(option: &str, value: &str, min: f64, max: f64) -> Result<f64, OptionsError> {
+    match value.parse::<f64>() {
+        Ok(number) if number > min && number < max => Ok(number),
+        _ => Err(OptionsError::InvalidValue(option.to_string(), value.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, OptionsError> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn invalid(args: &[&str]) -> (String, String) {
+        match parse(args) {
+            Err(OptionsError::InvalidValue(option, value)) => (option, value),
+            result => panic!("expected {:?} to be invalid, got {:?}", args, result),
+        }
+    }
+
+    #[test]
+    fn options_and_models() {
+        let options = parse(&["a.obj", "-s", "640x480", "--fov", "60", "-m", "solid", "-c", "b c.obj"]).unwrap();
+        assert_eq!(options.models, ["a.obj", "b c.obj"]);
+        assert_eq!(options.window_size, [640.0, 480.0]);
+        assert_eq!(options.projection.fov, Some(60.0));
+        assert_eq!(options.mode, RenderMode::Solid);
+        assert!(options.cull_backfaces);
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("-s", "1x2").unwrap(), [1.0, 2.0]);
+        for size in ["0x10", "10x0", "10", "10x", "x10", "-10x10", "10.5x10", "10x10x10"] {
+            assert_eq!(invalid(&["--size", size]), ("--size".to_string(), size.to_string()));
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        assert_eq!(parse_range("--fov", "179.5", 0.0, 180.0).unwrap(), 179.5);
+        for value in ["180", "0", "-45", "nan", "wide"] {
+            assert_eq!(invalid(&["--fov", value]), ("--fov".to_string(), value.to_string()));
+        }
+        assert!(parse_range("--near", "inf", 0.0, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn far_plane_must_be_beyond_the_near_plane() {
+        assert_eq!(
+            invalid(&["--near", "10", "--far", "10"]),
+            ("--far".to_string(), "10".to_string())
+        );
+        assert_eq!(
+            invalid(&["--far", "5", "--near", "10"]),
+            ("--far".to_string(), "5".to_string())
+        );
+
+        // one on its own is checked against each camera's other plane
+        let projection = Projection::default();
+        let options = parse(&["--near", "20000"]).unwrap();
+        assert!(matches!(
+            options.projection.apply(&projection),
+            Err(OptionsError::InvalidValue(option, _)) if option == "--near"
+        ));
+        let options = parse(&["--far", "0.5"]).unwrap();
+        assert!(matches!(
+            options.projection.apply(&projection),
+            Err(OptionsError::InvalidValue(option, _)) if option == "--far"
+        ));
+
+        let options = parse(&["--near", "2", "--fov", "90"]).unwrap();
+        let applied = options.projection.apply(&projection).unwrap();
+        assert_eq!(applied.fov, 90.0);
+        assert_eq!(applied.near, 2.0);
+        assert_eq!(applied.far, projection.far);
+    }
+
+    #[test]
+    fn missing_values_and_unknown_options() {
+        assert!(matches!(parse(&["a.obj", "--fov"]), Err(OptionsError::MissingValue(option)) if option == "--fov"));
+        assert!(matches!(parse(&["-o"]), Err(OptionsError::MissingValue(option)) if option == "-o"));
+        assert!(matches!(parse(&["-x"]), Err(OptionsError::UnknownOption(option)) if option == "-x"));
+        assert!(matches!(parse(&["--mode", "dots"]), Err(OptionsError::InvalidValue(..))));
+        assert!(matches!(parse(&["a.obj", "-h", "-x"]), Err(OptionsError::Help)));
+    }
+}
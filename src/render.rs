@@ -0,0 +1,806 @@
+use std::ops::AddAssign;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use image::{ImageError, Rgba, RgbaImage};
+use nalgebra::{Point2, Point3, Vector3, Vector4};
+
+use crate::camera::Camera;
+use crate::clip::{clip_line, clip_polygon, is_front_facing, Interpolate};
+use crate::lighting::{default_lights, Illumination, Lighting, Specular, Surface};
+use crate::material::Material;
+use crate::mesh::{FaceVertex, Object};
+use crate::scene::{Model, Scene};
+use crate::texture::{Filter, Texture};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    Wireframe,
+    Solid,
+}
+
+/// How solid surfaces are lit
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Shading {
+    /// Every face is drawn in the plain color of its material
+    Unlit,
+    /// Each face is lit as a whole, using its normal
+    Flat,
+    /// Each corner is lit using its smoothed normal, and the colors are blended across the face
+    Gouraud,
+    /// The smoothed normals are blended across the face and each pixel is lit using Phong's
+    /// reflection model
+    Phong,
+    /// Like Phong, but with Blinn's halfway vector for the highlights
+    BlinnPhong,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RenderOptions {
+    pub mode: RenderMode,
+    pub shading: Shading,
+    pub cull_backfaces: bool,
+    /// The color to use for faces without a material
+    pub default_color: [f32; 4],
+    pub texture_filter: Filter,
+    /// Draw the edges of the triangles each face is split into in wireframe mode, instead of the
+    /// edges of the faces themselves
+    pub show_triangles: bool,
+}
+
+/// How much work drawing took, for showing in the viewer
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    /// Polygons drawn at least partly, which are triangles when solid and in wireframe mode when
+    /// showing triangles, and faces otherwise
+    pub drawn: usize,
+    /// Polygons skipped because they faced away from the camera
+    pub backfacing: usize,
+    /// Polygons skipped because they were entirely outside the view
+    pub outside: usize,
+    /// Time spent transforming points into clip space
+    pub projecting: Duration,
+    /// Time spent clipping and drawing polygons
+    pub drawing: Duration,
+}
+
+impl RenderStats {
+    /// Polygons that weren't drawn for either reason
+    pub fn culled(&self) -> usize {
+        self.backfacing + self.outside
+    }
+}
+
+impl AddAssign for RenderStats {
+    fn add_assign(&mut self, other: Self) {
+        self.drawn += other.drawn;
+        self.backfacing += other.backfacing;
+        self.outside += other.outside;
+        self.projecting += other.projecting;
+        self.drawing += other.drawing;
+    }
+}
+
+/// An in-memory RGBA image with a depth buffer, that can be drawn into without a window or graphics context
+#[derive(Clone, Debug)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+    depth: Vec<f64>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize, color: [f32; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![color; width * height],
+            depth: vec![f64::INFINITY; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn size(&self) -> [f64; 2] {
+        [self.width as f64, self.height as f64]
+    }
+
+    /// Fill the image with the given color and reset the depth of every pixel
+    pub fn clear(&mut self, color: [f32; 4]) {
+        self.pixels.fill(color);
+        self.depth.fill(f64::INFINITY);
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> [f32; 4] {
+        self.pixels[y * self.width + x]
+    }
+
+    /// Blend the color onto the pixel using the color's alpha.  Pixels outside the image are ignored
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: [f32; 4]) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let pixel = &mut self.pixels[y * self.width + x];
+        let alpha = color[3];
+        for i in 0..3 {
+            pixel[i] = color[i] * alpha + pixel[i] * (1.0 - alpha);
+        }
+        pixel[3] = alpha + pixel[3] * (1.0 - alpha);
+    }
+
+    /// Draw a one pixel wide line, clipped to the image, between two points in window coordinates
+    pub fn draw_line(&mut self, from: [f64; 2], to: [f64; 2], color: [f32; 4]) {
+        let (from, to) = match clip_line_to_rect(from, to, [self.width as f64 - 1.0, self.height as f64 - 1.0]) {
+            Some(line) => line,
+            None => return,
+        };
+
+        let (dx, dy) = (to[0] - from[0], to[1] - from[1]);
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0);
+        for step in 0..=(steps as usize) {
+            let t = step as f64 / steps;
+            let x = (from[0] + dx * t).round() as usize;
+            let y = (from[1] + dy * t).round() as usize;
+            self.blend_pixel(x, y, color);
+        }
+    }
+
+    /// Fill a triangle given in window coordinates, with the depth of each vertex in z, drawing only the
+    /// pixels that are closer than what's already been drawn.  Pixels are included if their center is
+    /// inside the triangle, or on a top or left edge so that pixels on shared edges are only drawn once
+    pub fn fill_triangle(&mut self, vertices: [Point3<f64>; 3], color: [f32; 4]) {
+        self.fill_shaded_triangle(vertices, [1.0; 3], |_| color);
+    }
+
+    /// Fill a triangle like `fill_triangle`, asking the shader for the color of each pixel that's
+    /// drawn.  The shader is given the weights of each vertex at the pixel, which are corrected for
+    /// perspective using the reciprocal of each vertex's w in clip space, so that attributes blended
+    /// with them don't swim across the triangle
+    pub fn fill_shaded_triangle(
+        &mut self,
+        vertices: [Point3<f64>; 3],
+        inverse_w: [f64; 3],
+        mut shader: impl FnMut(&Fragment) -> [f32; 4],
+    ) {
+        let [a, mut b, mut c] = vertices;
+        let mut order = [0, 1, 2];
+        let mut area = edge_function(a, b, c.x, c.y);
+        if area < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+            order.swap(1, 2);
+            area = -area;
+        }
+        if area == 0.0 || !area.is_finite() || self.width == 0 || self.height == 0 {
+            return;
+        }
+
+        let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+        let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+        let max_x = a.x.max(b.x).max(c.x).ceil().min(self.width as f64 - 1.0);
+        let max_y = a.y.max(b.y).max(c.y).ceil().min(self.height as f64 - 1.0);
+        if max_x < 0.0 || max_y < 0.0 {
+            return;
+        }
+
+        let edges = [(b, c), (c, a), (a, b)];
+        for y in min_y..=(max_y as usize) {
+            for x in min_x..=(max_x as usize) {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+
+                let mut weights = [0.0; 3];
+                let inside = edges.iter().zip(weights.iter_mut()).all(|((from, to), weight)| {
+                    *weight = edge_function(*from, *to, px, py);
+                    *weight > 0.0 || (*weight == 0.0 && is_top_left(*from, *to))
+                });
+                if !inside {
+                    continue;
+                }
+
+                let z = (weights[0] * a.z + weights[1] * b.z + weights[2] * c.z) / area;
+                let i = y * self.width + x;
+                if z < self.depth[i] {
+                    self.depth[i] = z;
+
+                    let correct = |px: f64, py: f64| {
+                        let mut corrected = [0.0; 3];
+                        for ((from, to), vertex) in edges.iter().zip(order) {
+                            corrected[vertex] = edge_function(*from, *to, px, py) * inverse_w[vertex];
+                        }
+                        let total: f64 = corrected.iter().sum();
+                        corrected.map(|weight| weight / total)
+                    };
+                    let fragment = Fragment {
+                        weights: correct(px, py),
+                        right: correct(px + 1.0, py),
+                        below: correct(px, py + 1.0),
+                    };
+                    let color = shader(&fragment);
+                    self.blend_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    pub fn to_image(&self) -> RgbaImage {
+        RgbaImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = self.get_pixel(x as usize, y as usize);
+            Rgba(pixel.map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8))
+        })
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+        self.to_image().save_with_format(path, image::ImageFormat::Png)
+    }
+}
+
+/// The weights of each vertex of a triangle being filled, at a pixel and at its neighbours to the
+/// right and below, which may be outside the triangle.  Each set adds up to 1, and comparing them
+/// shows how quickly anything blended with them changes across the screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fragment {
+    pub weights: [f64; 3],
+    pub right: [f64; 3],
+    pub below: [f64; 3],
+}
+
+/// Twice the signed area of the triangle formed by the edge and the point, which is positive when
+/// the point is to the right of the edge (in window coordinates, where y points down)
+fn edge_function(from: Point3<f64>, to: Point3<f64>, x: f64, y: f64) -> f64 {
+    (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+}
+
+/// Whether the edge of a clockwise triangle (in window coordinates) is a top edge or a left edge
+fn is_top_left(from: Point3<f64>, to: Point3<f64>) -> bool {
+    (from.y == to.y && to.x > from.x) || to.y < from.y
+}
+
+/// Clip a line to the rectangle from (0, 0) to `max` using the Liang-Barsky algorithm, returning
+/// None if no part of the line is inside
+fn clip_line_to_rect(from: [f64; 2], to: [f64; 2], max: [f64; 2]) -> Option<([f64; 2], [f64; 2])> {
+    let delta = [to[0] - from[0], to[1] - from[1]];
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+
+    for axis in 0..2 {
+        for (p, q) in [(-delta[axis], from[axis]), (delta[axis], max[axis] - from[axis])] {
+            if p == 0.0 {
+                if q < 0.0 {
+                    return None;
+                }
+            } else {
+                let t = q / p;
+                if p < 0.0 {
+                    t0 = t0.max(t);
+                } else {
+                    t1 = t1.min(t);
+                }
+            }
+        }
+    }
+
+    if t0 > t1 || !t0.is_finite() || !t1.is_finite() {
+        return None;
+    }
+    let at = |t: f64| [from[0] + delta[0] * t, from[1] + delta[1] * t];
+    Some((at(t0), at(t1)))
+}
+
+/// Convert a clip space point inside the view frustum into window coordinates (where y points down),
+/// with the depth from -1 (near) to 1 (far) as the z coordinate
+pub fn get_point(point: Vector4<f64>, window_size: [f64; 2]) -> Point3<f64> {
+    let point = Point3::from_homogeneous(point).unwrap();
+    Point3::new(
+        ((point[0] + 1.0) / 2.0) * window_size[0],
+        ((1.0 - point[1]) / 2.0) * window_size[1],
+        point[2],
+    )
+}
+
+/// Clip the line between two projected points to the view frustum, and convert what's left into window coordinates
+pub fn get_line(points: &[Vector4<f64>], from: usize, to: usize, window_size: [f64; 2]) -> Option<([f64; 2], [f64; 2])> {
+    let (from, to) = clip_line(points[from], points[to])?;
+    let (from, to) = (get_point(from, window_size), get_point(to, window_size));
+    Some(([from.x, from.y], [to.x, to.y]))
+}
+
+/// Draw everything in the scene into the framebuffer as seen from the given camera, using the given
+/// render mode.  The depth buffer is shared, so models hide each other correctly.  A scene without
+/// any lights is lit by the default lights
+pub fn render(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, options: &RenderOptions) -> RenderStats {
+    let default_lights = default_lights();
+    let lights = if scene.lights.is_empty() {
+        &default_lights
+    } else {
+        &scene.lights
+    };
+
+    let mut stats = RenderStats::default();
+    for instance in scene.instances() {
+        let model = &scene.models[instance.model];
+        let material = instance.material.map(|material| &scene.materials[material]);
+
+        let start = Instant::now();
+        let points = model.object.project(&instance.world_from_model, camera, framebuffer.size());
+        let projected = Instant::now();
+        stats += match options.mode {
+            RenderMode::Wireframe => draw_wireframe(framebuffer, model, &points, material, options),
+            RenderMode::Solid => {
+                let lighting = Lighting::new(lights, &instance.world_from_model, camera.position);
+                draw_solid(framebuffer, model, &points, material, &lighting, options)
+            },
+        };
+        stats.projecting += projected - start;
+        stats.drawing += projected.elapsed();
+    }
+    stats
+}
+
+/// The color to draw a face with, which comes from the material overriding the object's own
+/// materials if there is one, then the face's material, then the default color
+pub fn face_color(object: &Object, face: usize, material: Option<&Material>, options: &RenderOptions) -> [f32; 4] {
+    material
+        .map(|material| material.diffuse_color())
+        .or_else(|| object.face_color(face))
+        .unwrap_or(options.default_color)
+}
+
+/// How a face is lit, which comes from the same material as its color.  Faces without a material
+/// have no highlights
+pub fn face_surface(object: &Object, face: usize, material: Option<&Material>, options: &RenderOptions) -> Surface {
+    material
+        .or_else(|| object.face_material(face))
+        .map(Surface::from_material)
+        .unwrap_or_else(|| Surface::matte(options.default_color))
+}
+
+/// The texture to draw a face with, from the same material as its color, along with how to sample
+/// it, which is up to the material if it says
+pub fn face_texture<'a>(
+    object: &'a Object,
+    face: usize,
+    material: Option<&'a Material>,
+    options: &RenderOptions,
+) -> Option<(&'a Texture, Filter)> {
+    let material = material.or_else(|| object.face_material(face))?;
+    let texture = material.diffuse_texture.as_deref()?;
+    Some((texture, material.texture_filter.unwrap_or(options.texture_filter)))
+}
+
+/// Draw every visible face of the model as a wireframe into the framebuffer, using the same
+/// projection as the viewer, and count the faces drawn and skipped
+pub fn draw_wireframe(
+    framebuffer: &mut Framebuffer,
+    model: &Model,
+    points: &[Vector4<f64>],
+    material: Option<&Material>,
+    options: &RenderOptions,
+) -> RenderStats {
+    let window_size = framebuffer.size();
+    wireframe_lines(model, points, material, window_size, options, |from, to, color| {
+        framebuffer.draw_line(from, to, color)
+    })
+}
+
+/// Work out the wireframe of everything in the scene as seen from the given camera, calling
+/// `draw_line` with the ends of each line in window coordinates and its color, so that it can be
+/// drawn with any graphics library
+pub fn wireframe(
+    scene: &Scene,
+    camera: &Camera,
+    window_size: [f64; 2],
+    options: &RenderOptions,
+    mut draw_line: impl FnMut([f64; 2], [f64; 2], [f32; 4]),
+) -> RenderStats {
+    let mut stats = RenderStats::default();
+    for instance in scene.instances() {
+        let model = &scene.models[instance.model];
+        let material = instance.material.map(|material| &scene.materials[material]);
+
+        let start = Instant::now();
+        let points = model.object.project(&instance.world_from_model, camera, window_size);
+        let projected = Instant::now();
+        stats += wireframe_lines(model, &points, material, window_size, options, &mut draw_line);
+        stats.projecting += projected - start;
+        stats.drawing += projected.elapsed();
+    }
+    stats
+}
+
+/// Cull the model's visible faces, or their triangles if the options say, and clip their edges to
+/// the view, calling `draw_line` for each edge that's left, and count the polygons drawn and skipped
+fn wireframe_lines(
+    model: &Model,
+    points: &[Vector4<f64>],
+    material: Option<&Material>,
+    window_size: [f64; 2],
+    options: &RenderOptions,
+    mut draw_line: impl FnMut([f64; 2], [f64; 2], [f32; 4]),
+) -> RenderStats {
+    let object = &model.object;
+    let polygons: Vec<(&[FaceVertex], usize)> = if options.show_triangles {
+        model
+            .triangles
+            .iter()
+            .map(|triangle| (&triangle.vertices[..], triangle.face))
+            .collect()
+    } else {
+        object
+            .faces
+            .iter()
+            .enumerate()
+            .map(|(i, face)| (&face.vertices[..], i))
+            .collect()
+    };
+    let visible = object.face_visibility();
+    let mut stats = RenderStats::default();
+
+    for (vertices, face) in polygons.into_iter().filter(|(_, face)| visible[*face]) {
+        if options.cull_backfaces && !is_front_facing(&vertices.iter().map(|vertex| points[vertex.point]).collect::<Vec<_>>()) {
+            stats.backfacing += 1;
+            continue;
+        }
+
+        let color = face_color(object, face, material, options);
+        let mut drawn = false;
+        for (i, from) in vertices.iter().enumerate() {
+            let to = vertices[(i + 1) % vertices.len()];
+            if let Some((p1, p2)) = get_line(points, from.point, to.point, window_size) {
+                draw_line(p1, p2, color);
+                drawn = true;
+            }
+        }
+        if drawn {
+            stats.drawn += 1;
+        } else {
+            stats.outside += 1;
+        }
+    }
+    stats
+}
+
+/// What's carried along with each corner of a triangle as it's clipped and filled, in world space
+#[derive(Copy, Clone, Debug)]
+struct Varying {
+    position: Point3<f64>,
+    normal: Vector3<f64>,
+    texcoord: Point2<f64>,
+    light: Illumination,
+}
+
+impl Varying {
+    fn blend(corners: &[Varying; 3], weights: [f64; 3]) -> Self {
+        let mut blended = Self {
+            position: Point3::origin(),
+            normal: Vector3::zeros(),
+            texcoord: Point2::origin(),
+            light: Illumination {
+                diffuse: Vector3::zeros(),
+                specular: Vector3::zeros(),
+            },
+        };
+        for (corner, weight) in corners.iter().zip(weights) {
+            blended.position += corner.position.coords * weight;
+            blended.normal += corner.normal * weight;
+            blended.texcoord += corner.texcoord.coords * weight;
+            blended.light.diffuse += corner.light.diffuse * weight;
+            blended.light.specular += corner.light.specular * weight;
+        }
+        blended
+    }
+
+    fn blend_texcoords(corners: &[Varying; 3], weights: [f64; 3]) -> Point2<f64> {
+        corners
+            .iter()
+            .zip(weights)
+            .fold(Point2::origin(), |texcoord, (corner, weight)| {
+                texcoord + corner.texcoord.coords * weight
+            })
+    }
+}
+
+impl Interpolate for Varying {
+    fn interpolate(&self, other: &Self, t: f64) -> Self {
+        Self::blend(&[*self, *other, *other], [1.0 - t, t, 0.0])
+    }
+}
+
+/// Draw the visible triangles of the model as solid polygons into the framebuffer, using the depth
+/// buffer to hide surfaces that are behind others.  Faces seen from behind are lit as if their
+/// normals pointed the other way, so the inside of an open model isn't left in the dark.  Faces
+/// with a diffuse texture and texture coordinates at every corner are textured, with the texture
+/// tinted by the material's diffuse color.  The triangles drawn and skipped are counted
+pub fn draw_solid(
+    framebuffer: &mut Framebuffer,
+    model: &Model,
+    points: &[Vector4<f64>],
+    material: Option<&Material>,
+    lighting: &Lighting,
+    options: &RenderOptions,
+) -> RenderStats {
+    let window_size = framebuffer.size();
+    let object = &model.object;
+    let visible = object.face_visibility();
+    let mut stats = RenderStats::default();
+
+    for (triangle, normals) in model.triangles.iter().zip(&model.vertex_normals) {
+        if !visible[triangle.face] {
+            continue;
+        }
+
+        let front_facing = is_front_facing(&triangle.vertices.map(|vertex| points[vertex.point]));
+        if options.cull_backfaces && !front_facing {
+            stats.backfacing += 1;
+            continue;
+        }
+        let side = if front_facing { 1.0 } else { -1.0 };
+
+        let surface = face_surface(object, triangle.face, material, options);
+        let texture = face_texture(object, triangle.face, material, options)
+            .filter(|_| triangle.vertices.iter().all(|vertex| vertex.texcoord.is_some()));
+        let face_light = match options.shading {
+            Shading::Unlit => Illumination::unlit(),
+            _ => {
+                let normal = side * lighting.world_normal(&model.face_normals[triangle.face]);
+                lighting.illuminate(&surface, &normal, &Point3::origin(), Specular::None)
+            },
+        };
+
+        let corners: Vec<(Vector4<f64>, Varying)> = triangle
+            .vertices
+            .iter()
+            .zip(normals)
+            .map(|(vertex, normal)| {
+                let position = lighting.world_point(&object.points[vertex.point]);
+                let normal = side * lighting.world_normal(normal);
+                let light = match options.shading {
+                    Shading::Gouraud => lighting.illuminate(&surface, &normal, &position, Specular::BlinnPhong),
+                    _ => face_light,
+                };
+                let texcoord = vertex
+                    .texcoord
+                    .map_or(Point2::origin(), |texcoord| object.texcoords[texcoord]);
+                (
+                    points[vertex.point],
+                    Varying {
+                        position,
+                        normal,
+                        texcoord,
+                        light,
+                    },
+                )
+            })
+            .collect();
+
+        let clipped = clip_polygon(&corners);
+        if clipped.len() < 3 {
+            stats.outside += 1;
+            continue;
+        }
+        stats.drawn += 1;
+
+        // the clipped polygon is still convex, so it can be split into a fan
+        let projected: Vec<(Point3<f64>, f64)> = clipped
+            .iter()
+            .map(|(point, _)| (get_point(*point, window_size), 1.0 / point.w))
+            .collect();
+        for i in 1..projected.len() - 1 {
+            let fan = [0, i, i + 1];
+            let vertices = fan.map(|j| projected[j].0);
+            let inverse_w = fan.map(|j| projected[j].1);
+            let varyings = fan.map(|j| clipped[j].1);
+
+            let flat = matches!(options.shading, Shading::Unlit | Shading::Flat);
+            if flat && texture.is_none() {
+                framebuffer.fill_triangle(vertices, face_light.apply(surface.color));
+                continue;
+            }
+
+            framebuffer.fill_shaded_triangle(vertices, inverse_w, |fragment| {
+                let varying = Varying::blend(&varyings, fragment.weights);
+                let mut color = surface.color;
+                if let Some((texture, filter)) = texture {
+                    let texcoord_at = |weights: [f64; 3]| Varying::blend_texcoords(&varyings, weights);
+                    let derivatives = [
+                        texcoord_at(fragment.right) - varying.texcoord,
+                        texcoord_at(fragment.below) - varying.texcoord,
+                    ];
+                    let texel = texture.sample(varying.texcoord, derivatives, filter);
+                    color = [0, 1, 2, 3].map(|i| color[i] * texel[i]);
+                }
+
+                let light = match options.shading {
+                    Shading::Phong | Shading::BlinnPhong => {
+                        let specular = if options.shading == Shading::Phong {
+                            Specular::Phong
+                        } else {
+                            Specular::BlinnPhong
+                        };
+                        let normal = varying.normal.try_normalize(0.0).unwrap_or(varying.normal);
+                        lighting.illuminate(&surface, &normal, &varying.position, specular)
+                    },
+                    _ => varying.light,
+                };
+                light.apply(color)
+            });
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::Projection;
+    use crate::mesh::Face;
+    use crate::scene::{ModelSource, Node, Transform};
+
+    const WHITE: [f32; 4] = [1.0; 4];
+    const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+    /// The pixels that aren't the background color
+    fn drawn(framebuffer: &Framebuffer) -> Vec<(usize, usize)> {
+        let mut pixels = vec![];
+        for y in 0..framebuffer.height() {
+            for x in 0..framebuffer.width() {
+                if framebuffer.get_pixel(x, y) != WHITE {
+                    pixels.push((x, y));
+                }
+            }
+        }
+        pixels
+    }
+
+    #[test]
+    fn line_inside_the_image() {
+        let mut framebuffer = Framebuffer::new(10, 5, WHITE);
+        framebuffer.draw_line([1.0, 2.0], [5.0, 2.0], RED);
+        assert_eq!(drawn(&framebuffer), (1..=5).map(|x| (x, 2)).collect::<Vec<_>>());
+        assert_eq!(framebuffer.get_pixel(3, 2), RED);
+    }
+
+    #[test]
+    fn line_with_ends_outside_the_image_is_clipped() {
+        let mut framebuffer = Framebuffer::new(10, 5, WHITE);
+        framebuffer.draw_line([-10.0, 2.0], [30.0, 2.0], RED);
+        assert_eq!(drawn(&framebuffer), (0..10).map(|x| (x, 2)).collect::<Vec<_>>());
+
+        let mut framebuffer = Framebuffer::new(10, 10, WHITE);
+        framebuffer.draw_line([-5.0, -5.0], [20.0, 20.0], RED);
+        assert_eq!(drawn(&framebuffer), (0..10).map(|i| (i, i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn line_outside_the_image_draws_nothing() {
+        let mut framebuffer = Framebuffer::new(10, 5, WHITE);
+        framebuffer.draw_line([-10.0, -1.0], [30.0, -1.0], RED);
+        framebuffer.draw_line([12.0, 0.0], [20.0, 4.0], RED);
+        framebuffer.draw_line([-3.0, 8.0], [5.0, 20.0], RED);
+        assert!(drawn(&framebuffer).is_empty());
+    }
+
+    #[test]
+    fn clip_line_to_rectangle() {
+        assert_eq!(
+            clip_line_to_rect([1.0, 1.0], [2.0, 3.0], [9.0, 4.0]),
+            Some(([1.0, 1.0], [2.0, 3.0]))
+        );
+        assert_eq!(
+            clip_line_to_rect([-2.0, 1.0], [12.0, 1.0], [9.0, 4.0]),
+            Some(([0.0, 1.0], [9.0, 1.0]))
+        );
+        assert_eq!(
+            clip_line_to_rect([4.0, -4.0], [4.0, 8.0], [9.0, 4.0]),
+            Some(([4.0, 0.0], [4.0, 4.0]))
+        );
+        // passing by a corner without going through the rectangle
+        assert_eq!(clip_line_to_rect([-2.0, 3.0], [3.0, 8.0], [9.0, 4.0]), None);
+        assert_eq!(clip_line_to_rect([0.0, 5.0], [9.0, 5.0], [9.0, 4.0]), None);
+    }
+
+    /// The pixels a triangle covers, drawn on its own
+    fn covered(vertices: [Point3<f64>; 3]) -> Vec<(usize, usize)> {
+        let mut framebuffer = Framebuffer::new(8, 8, WHITE);
+        framebuffer.fill_triangle(vertices, RED);
+        drawn(&framebuffer)
+    }
+
+    #[test]
+    fn shared_edges_are_drawn_once() {
+        // a square split along its diagonal, with every edge running through pixel centers
+        let corners = [
+            Point3::new(1.5, 1.5, 0.0),
+            Point3::new(5.5, 1.5, 0.0),
+            Point3::new(5.5, 5.5, 0.0),
+            Point3::new(1.5, 5.5, 0.0),
+        ];
+        let upper = covered([corners[0], corners[1], corners[2]]);
+        let lower = covered([corners[0], corners[2], corners[3]]);
+        assert!(upper.iter().all(|pixel| !lower.contains(pixel)));
+
+        // the top and left edges of the square are drawn, and the bottom and right edges aren't
+        let mut square: Vec<_> = upper.into_iter().chain(lower).collect();
+        square.sort_unstable_by_key(|&(x, y)| (y, x));
+        let expected: Vec<_> = (1..5).flat_map(|y| (1..5).map(move |x| (x, y))).collect();
+        assert_eq!(square, expected);
+
+        // the winding doesn't matter
+        assert_eq!(
+            covered([corners[0], corners[1], corners[2]]),
+            covered([corners[0], corners[2], corners[1]])
+        );
+    }
+
+    #[test]
+    fn nearer_triangle_wins_in_either_order() {
+        const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+        let triangle = |z| [Point3::new(0.0, 0.0, z), Point3::new(8.0, 0.0, z), Point3::new(0.0, 8.0, z)];
+        let (near, far) = (triangle(0.25), triangle(0.75));
+
+        let mut framebuffer = Framebuffer::new(8, 8, WHITE);
+        framebuffer.fill_triangle(near, RED);
+        framebuffer.fill_triangle(far, BLUE);
+        assert_eq!(framebuffer.get_pixel(2, 2), RED);
+
+        framebuffer.clear(WHITE);
+        framebuffer.fill_triangle(far, BLUE);
+        framebuffer.fill_triangle(near, RED);
+        assert_eq!(framebuffer.get_pixel(2, 2), RED);
+    }
+
+    #[test]
+    fn render_single_triangle() {
+        let vertex = |point| FaceVertex {
+            point,
+            texcoord: None,
+            normal: None,
+        };
+        let object = Object {
+            points: vec![
+                Point3::new(-2.0, -2.0, 0.0),
+                Point3::new(2.0, -2.0, 0.0),
+                Point3::new(0.0, 2.0, 0.0),
+            ],
+            texcoords: vec![],
+            normals: vec![],
+            faces: vec![Face {
+                vertices: vec![vertex(0), vertex(1), vertex(2)],
+                material: None,
+                smoothing_group: None,
+            }],
+            materials: vec![],
+            groups: vec![],
+        };
+        let mut scene = Scene::new();
+        let model = scene.add_model(Model::new("triangle", ModelSource::File("triangle.obj".into()), object));
+        scene
+            .nodes
+            .push(Node::new("triangle", Transform::from_translation(Vector3::new(0.0, 0.0, 10.0))).with_model(model));
+
+        let options = RenderOptions {
+            mode: RenderMode::Solid,
+            shading: Shading::Unlit,
+            cull_backfaces: false,
+            default_color: RED,
+            texture_filter: Filter::Nearest,
+            show_triangles: false,
+        };
+        let mut framebuffer = Framebuffer::new(20, 20, WHITE);
+        let stats = render(&mut framebuffer, &scene, &Camera::new(Projection::default()), &options);
+        assert_eq!(stats.drawn, 1);
+
+        // the triangle points up, in the middle of the image
+        assert_eq!(framebuffer.get_pixel(10, 10), RED);
+        assert_eq!(framebuffer.get_pixel(10, 6), RED);
+        assert_eq!(framebuffer.get_pixel(7, 13), RED);
+        for (x, y) in [(0, 0), (19, 0), (0, 19), (19, 19), (7, 6), (13, 6)] {
+            assert_eq!(framebuffer.get_pixel(x, y), WHITE, "pixel ({}, {})", x, y);
+        }
+    }
+}
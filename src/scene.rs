@@ -0,0 +1,750 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use nalgebra::{Matrix4, Point3, UnitQuaternion, Vector3};
+
+use crate::camera::{Camera, Projection};
+use crate::error::{LoadError, LoadErrorKind};
+use crate::lighting::Light;
+use crate::material::Material;
+use crate::math;
+use crate::mesh::{parse_floats, Object, Triangle};
+
+/// A node's position, orientation and size relative to its parent
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f64>,
+    pub rotation: UnitQuaternion<f64>,
+    pub scale: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translation: Vector3::zeros(),
+            rotation: UnitQuaternion::identity(),
+            scale: 1.0,
+        }
+    }
+}
+
+impl Transform {
+    pub fn from_translation(translation: Vector3<f64>) -> Self {
+        Self {
+            translation,
+            ..Self::default()
+        }
+    }
+
+    /// The transform from the node's space into its parent's space, which scales, then rotates, then translates
+    pub fn matrix(&self) -> Matrix4<f64> {
+        math::translate(Point3::from(self.translation)) * math::scale(self.scale) * self.rotation.to_homogeneous()
+    }
+}
+
+/// A continuous rotation of a node around one of its own axes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Spin {
+    pub axis: Vector3<f64>,
+    /// The number of degrees to turn each second
+    pub speed: f64,
+}
+
+/// Where a model's object came from, so that the scene can be saved and loaded again
+#[derive(Clone, Debug, PartialEq)]
+pub enum ModelSource {
+    /// Read from an OBJ file
+    File(PathBuf),
+    /// Split off from another model, which loses the named groups
+    Split { model: String, groups: Vec<String> },
+}
+
+/// A loaded object, along with its triangles and normals so they only need to be worked out once
+#[derive(Clone, Debug)]
+pub struct Model {
+    pub name: String,
+    pub source: ModelSource,
+    pub object: Object,
+    pub triangles: Vec<Triangle>,
+    /// The normal of each of the object's faces
+    pub face_normals: Vec<Vector3<f64>>,
+    /// The normals at the corners of each triangle, for smooth shading
+    pub vertex_normals: Vec<[Vector3<f64>; 3]>,
+}
+
+impl Model {
+    pub fn new(name: &str, source: ModelSource, object: Object) -> Self {
+        let mut model = Self {
+            name: name.to_string(),
+            source,
+            object,
+            triangles: vec![],
+            face_normals: vec![],
+            vertex_normals: vec![],
+        };
+        model.update_geometry();
+        model
+    }
+
+    /// Work out the triangles and normals again, after the object's faces have changed
+    pub fn update_geometry(&mut self) {
+        self.triangles = self.object.triangulate();
+        self.face_normals = self.object.face_normals();
+        self.vertex_normals = self.object.vertex_normals(&self.triangles);
+    }
+
+    pub fn read(name: &str, filename: impl AsRef<Path>) -> Result<Model, LoadError> {
+        let object = Object::read(filename.as_ref())?;
+        Ok(Model::new(name, ModelSource::File(filename.as_ref().to_path_buf()), object))
+    }
+
+    /// Move the faces in any of the named groups into a new model, so they can be placed separately
+    pub fn split(&mut self, name: &str, groups: &[&str]) -> Model {
+        let object = self.object.split_groups(groups);
+        self.update_geometry();
+        let source = ModelSource::Split {
+            model: self.name.clone(),
+            groups: groups.iter().map(|group| group.to_string()).collect(),
+        };
+        Model::new(name, source, object)
+    }
+}
+
+/// Something placed in the scene, which can draw a model and carries its children along with it
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,
+    /// Index into the scene's models
+    pub model: Option<usize>,
+    /// Index into the scene's materials, used for every face of the model instead of its own materials
+    pub material: Option<usize>,
+    pub spin: Option<Spin>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    pub fn new(name: &str, transform: Transform) -> Self {
+        Self {
+            name: name.to_string(),
+            transform,
+            model: None,
+            material: None,
+            spin: None,
+            children: vec![],
+        }
+    }
+
+    pub fn with_model(mut self, model: usize) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    pub fn with_spin(mut self, spin: Spin) -> Self {
+        self.spin = Some(spin);
+        self
+    }
+
+    pub fn with_child(mut self, child: Node) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    fn collect_instances(&self, parent: &Matrix4<f64>, instances: &mut Vec<Instance>) {
+        let world_from_node = parent * self.transform.matrix();
+        if let Some(model) = self.model {
+            instances.push(Instance {
+                model,
+                world_from_model: world_from_node,
+                material: self.material,
+            });
+        }
+        for child in &self.children {
+            child.collect_instances(&world_from_node, instances);
+        }
+    }
+
+    fn animate(&mut self, dt: f64) {
+        if let Some(spin) = self.spin {
+            let rotation = math::quaternion_from_axis_angle(spin.axis, spin.speed * dt);
+            self.transform.rotation *= rotation;
+            self.transform.rotation.renormalize_fast();
+        }
+        for child in &mut self.children {
+            child.animate(dt);
+        }
+    }
+}
+
+/// A model to draw, and where to draw it
+#[derive(Copy, Clone, Debug)]
+pub struct Instance {
+    /// Index into the scene's models
+    pub model: usize,
+    pub world_from_model: Matrix4<f64>,
+    /// Index into the scene's materials, if the node overrides the model's own materials
+    pub material: Option<usize>,
+}
+
+/// The models and the tree of nodes that place them in the world, which can be shared between nodes
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub models: Vec<Model>,
+    /// The nodes at the top of the tree, which are placed directly in world space
+    pub nodes: Vec<Node>,
+    /// The material libraries that have been read, and the materials from them that nodes can use
+    pub material_libraries: Vec<PathBuf>,
+    pub materials: Vec<Material>,
+    pub lights: Vec<Light>,
+    /// Named places to view the scene from
+    pub cameras: Vec<(String, Camera)>,
+}
+
+/// Something in a scene file that the statements following it apply to, up to its `end`
+enum Block {
+    Node(Node),
+    Camera(String, Camera),
+}
+
+/// The parser state for reading a scene file
+struct ReadState {
+    dir: PathBuf,
+    /// The blocks that are open, with the innermost last
+    blocks: Vec<Block>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a model to the scene, returning its index for nodes to refer to
+    pub fn add_model(&mut self, model: Model) -> usize {
+        self.models.push(model);
+        self.models.len() - 1
+    }
+
+    /// Every model drawn by a node, along with where to draw it
+    pub fn instances(&self) -> Vec<Instance> {
+        let mut instances = vec![];
+        for node in &self.nodes {
+            node.collect_instances(&Matrix4::identity(), &mut instances);
+        }
+        instances
+    }
+
+    /// Move every spinning node on by the given number of seconds
+    pub fn animate(&mut self, dt: f64) {
+        for node in &mut self.nodes {
+            node.animate(dt);
+        }
+    }
+
+    /// Read a scene file and load the models it uses, with paths relative to the scene file
+    pub fn read(filename: impl AsRef<Path>) -> Result<Scene, LoadError> {
+        let filename = filename.as_ref();
+        let file = File::open(filename).map_err(|err| LoadError::new(filename, None, err.into()))?;
+        let reader = BufReader::new(file);
+
+        let mut scene = Scene::new();
+        let mut state = ReadState {
+            dir: filename.parent().unwrap_or(Path::new("")).to_path_buf(),
+            blocks: vec![],
+        };
+
+        for (i, line) in reader.lines().enumerate() {
+            scene
+                .read_line(&mut state, line)
+                .map_err(|kind| LoadError::new(filename, Some(i + 1), kind))?;
+        }
+        if !state.blocks.is_empty() {
+            let kind = LoadErrorKind::Unexpected("end of file, expecting \"end\"".to_string());
+            return Err(LoadError::new(filename, None, kind));
+        }
+
+        Ok(scene)
+    }
+
+    fn read_line(&mut self, state: &mut ReadState, line: Result<String, io::Error>) -> Result<(), LoadErrorKind> {
+        let line = line?;
+        let mut words = line.split_whitespace();
+        let line_type = match words.next() {
+            Some(line_type) if !line_type.starts_with('#') => line_type,
+            _ => return Ok(()),
+        };
+
+        match (line_type, state.blocks.last_mut()) {
+            ("mtllib", _) => {
+                let path = state.dir.join(rest_of_line("mtllib", &line, 0)?);
+                let materials = Material::read_library(&path).map_err(|err| LoadErrorKind::Referenced(Box::new(err)))?;
+                self.materials.extend(materials);
+                self.material_libraries.push(path);
+            },
+            ("model", _) => {
+                let name = next_word("model", &mut words)?;
+                let path = state.dir.join(rest_of_line("model", &line, 1)?);
+                let model = Model::read(name, path).map_err(|err| LoadErrorKind::Referenced(Box::new(err)))?;
+                self.add_model(model);
+            },
+            ("split", _) => {
+                let (name, source) = (next_word("split", &mut words)?, next_word("split", &mut words)?);
+                let source = self.find_model(source)?;
+                let groups: Vec<&str> = words.collect();
+                let object = &self.models[source].object;
+                if let Some(group) = groups
+                    .iter()
+                    .find(|name| !object.groups.iter().any(|group| group.name == **name))
+                {
+                    return Err(LoadErrorKind::UnknownName {
+                        element: "group",
+                        name: group.to_string(),
+                    });
+                }
+                let model = self.models[source].split(name, &groups);
+                self.add_model(model);
+            },
+            ("hide", _) => {
+                let model = self.find_model(next_word("hide", &mut words)?)?;
+                for group in words {
+                    if !self.models[model].object.set_group_visible(group, false) {
+                        return Err(LoadErrorKind::UnknownName {
+                            element: "group",
+                            name: group.to_string(),
+                        });
+                    }
+                }
+            },
+            ("light", _) => {
+                let light = match next_word("light", &mut words)? {
+                    "ambient" => Light::Ambient {
+                        color: to_vector(&parse_values("light", 3, words)?),
+                    },
+                    "directional" => {
+                        let values = parse_values("light", 6, words)?;
+                        Light::Directional {
+                            direction: to_vector(&values),
+                            color: to_vector(&values[3..]),
+                        }
+                    },
+                    kind => return Err(LoadErrorKind::Parse(kind.to_string())),
+                };
+                self.lights.push(light);
+            },
+            ("node", None | Some(Block::Node(_))) => {
+                let name = words.collect::<Vec<&str>>().join(" ");
+                state.blocks.push(Block::Node(Node::new(&name, Transform::default())));
+            },
+            ("camera", None) => {
+                let name = words.collect::<Vec<&str>>().join(" ");
+                state.blocks.push(Block::Camera(name, Camera::new(Default::default())));
+            },
+            ("end", Some(_)) => match state.blocks.pop() {
+                Some(Block::Node(node)) => match state.blocks.last_mut() {
+                    Some(Block::Node(parent)) => parent.children.push(node),
+                    _ => self.nodes.push(node),
+                },
+                // the near and far planes can come in either order, so they're only checked at the end
+                Some(Block::Camera(name, camera)) => {
+                    let Projection {
+                        near,
+                        far,
+                        ..
+                    } = camera.projection;
+                    if far <= near {
+                        return Err(LoadErrorKind::Unexpected(format!(
+                            "far distance {} for camera {:?}, which isn't beyond the near distance {}",
+                            far, name, near
+                        )));
+                    }
+                    self.cameras.push((name, camera))
+                },
+                None => {},
+            },
+
+            ("position", Some(Block::Node(node))) => node.transform.translation = to_vector(&parse_values("position", 3, words)?),
+            ("rotation", Some(Block::Node(node))) => {
+                node.transform.rotation = math::quaternion_from_euler(to_vector(&parse_values("rotation", 3, words)?));
+            },
+            ("scale", Some(Block::Node(node))) => node.transform.scale = parse_values("scale", 1, words)?[0],
+            ("mesh", Some(Block::Node(node))) => node.model = Some(self.find_model(only_word("mesh", words)?)?),
+            ("material", Some(Block::Node(node))) => {
+                let name = words.collect::<Vec<&str>>().join(" ");
+                match self.materials.iter().position(|material| material.name == name) {
+                    Some(material) => node.material = Some(material),
+                    None => {
+                        return Err(LoadErrorKind::UnknownName {
+                            element: "material",
+                            name,
+                        })
+                    },
+                }
+            },
+            ("spin", Some(Block::Node(node))) => {
+                let values = parse_values("spin", 4, words)?;
+                node.spin = Some(Spin {
+                    axis: to_vector(&values),
+                    speed: values[3],
+                });
+            },
+
+            ("position", Some(Block::Camera(_, camera))) => {
+                camera.position = Point3::from(to_vector(&parse_values("position", 3, words)?));
+            },
+            ("rotation", Some(Block::Camera(_, camera))) => {
+                camera.set_euler_angles(to_vector(&parse_values("rotation", 3, words)?))
+            },
+            ("fov", Some(Block::Camera(_, camera))) => camera.projection.fov = parse_range("fov", words, 0.0, 180.0)?,
+            ("near", Some(Block::Camera(_, camera))) => camera.projection.near = parse_range("near", words, 0.0, f64::INFINITY)?,
+            ("far", Some(Block::Camera(_, camera))) => camera.projection.far = parse_range("far", words, 0.0, f64::INFINITY)?,
+
+            // anything else is either misplaced, such as a node inside a camera, or not part of the format
+            (line_type, _) => return Err(LoadErrorKind::Unexpected(format!("{:?}", line_type))),
+        }
+        Ok(())
+    }
+
+    fn find_model(&self, name: &str) -> Result<usize, LoadErrorKind> {
+        self.models
+            .iter()
+            .position(|model| model.name == name)
+            .ok_or_else(|| LoadErrorKind::UnknownName {
+                element: "model",
+                name: name.to_string(),
+            })
+    }
+
+    /// Write the scene out in the format `read` uses, with paths relative to the new file where possible
+    pub fn save(&self, filename: impl AsRef<Path>) -> io::Result<()> {
+        let filename = filename.as_ref();
+        let dir = filename.parent().unwrap_or(Path::new(""));
+        let mut file = BufWriter::new(File::create(filename)?);
+
+        for path in &self.material_libraries {
+            writeln!(file, "mtllib {}", relative_path(path, dir).display())?;
+        }
+        for model in &self.models {
+            match &model.source {
+                ModelSource::File(path) => writeln!(file, "model {} {}", model.name, relative_path(path, dir).display())?,
+                ModelSource::Split {
+                    model: source,
+                    groups,
+                } => writeln!(file, "split {} {} {}", model.name, source, groups.join(" "))?,
+            }
+        }
+        for model in &self.models {
+            let hidden: Vec<&str> = model
+                .object
+                .groups
+                .iter()
+                .filter(|group| !group.visible)
+                .map(|group| group.name.as_str())
+                .collect();
+            if !hidden.is_empty() {
+                writeln!(file, "hide {} {}", model.name, hidden.join(" "))?;
+            }
+        }
+
+        if !self.lights.is_empty() {
+            writeln!(file)?;
+        }
+        for light in &self.lights {
+            match light {
+                Light::Ambient {
+                    color,
+                } => writeln!(file, "light ambient {}", format_vector(color))?,
+                Light::Directional {
+                    direction,
+                    color,
+                } => writeln!(
+                    file,
+                    "light directional {} {}",
+                    format_vector(direction),
+                    format_vector(color)
+                )?,
+            }
+        }
+
+        for (name, camera) in &self.cameras {
+            writeln!(file, "\ncamera {}", name)?;
+            writeln!(file, "    position {}", format_vector(&camera.position.coords))?;
+            writeln!(file, "    rotation {}", format_vector(&camera.euler_angles()))?;
+            writeln!(file, "    fov {}", camera.projection.fov)?;
+            writeln!(file, "    near {}", camera.projection.near)?;
+            writeln!(file, "    far {}", camera.projection.far)?;
+            writeln!(file, "end")?;
+        }
+
+        for node in &self.nodes {
+            writeln!(file)?;
+            self.write_node(&mut file, node, 0)?;
+        }
+        file.flush()
+    }
+
+    fn write_node(&self, file: &mut impl Write, node: &Node, depth: usize) -> io::Result<()> {
+        let indent = "    ".repeat(depth);
+        writeln!(file, "{}node {}", indent, node.name)?;
+        writeln!(file, "{}    position {}", indent, format_vector(&node.transform.translation))?;
+        if node.transform.rotation != UnitQuaternion::identity() {
+            let angles = math::euler_from_quaternion(&node.transform.rotation);
+            writeln!(file, "{}    rotation {}", indent, format_vector(&angles))?;
+        }
+        if node.transform.scale != 1.0 {
+            writeln!(file, "{}    scale {}", indent, node.transform.scale)?;
+        }
+        if let Some(model) = node.model {
+            writeln!(file, "{}    mesh {}", indent, self.models[model].name)?;
+        }
+        if let Some(material) = node.material {
+            writeln!(file, "{}    material {}", indent, self.materials[material].name)?;
+        }
+        if let Some(spin) = node.spin {
+            writeln!(file, "{}    spin {} {}", indent, format_vector(&spin.axis), spin.speed)?;
+        }
+        for child in &node.children {
+            self.write_node(file, child, depth + 1)?;
+        }
+        writeln!(file, "{}end", indent)
+    }
+}
+
+/// The single word left on the line, where anything more is an error
+fn only_word<'a>(element: &'static str, words: impl Iterator<Item = &'a str>) -> Result<&'a str, LoadErrorKind> {
+    let words: Vec<&str> = words.collect();
+    match words[..] {
+        [word] => Ok(word),
+        _ => Err(LoadErrorKind::WrongArity {
+            element,
+            expected: 1,
+            found: words.len(),
+        }),
+    }
+}
+
+/// The rest of the line after the line type and the given number of words before the path, with
+/// any spaces inside it kept, for paths that can have spaces in them
+fn rest_of_line<'a>(element: &'static str, line: &'a str, words_before: usize) -> Result<&'a str, LoadErrorKind> {
+    let mut rest = line.trim();
+    for _ in 0..=words_before {
+        rest = rest.split_once(char::is_whitespace).map_or("", |(_, rest)| rest.trim_start());
+    }
+    if rest.is_empty() {
+        return Err(LoadErrorKind::WrongArity {
+            element,
+            expected: words_before + 1,
+            found: line.split_whitespace().count() - 1,
+        });
+    }
+    Ok(rest)
+}
+
+/// Parse exactly the expected number of values
+fn parse_values<'a>(
+    element: &'static str,
+    expected: usize,
+    words: impl Iterator<Item = &'a str>,
+) -> Result<Vec<f64>, LoadErrorKind> {
+    let values = parse_floats(element, expected, words)?;
+    if values.len() > expected {
+        return Err(LoadErrorKind::WrongArity {
+            element,
+            expected,
+            found: values.len(),
+        });
+    }
+    Ok(values)
+}
+
+fn next_word<'a>(element: &'static str, words: &mut impl Iterator<Item = &'a str>) -> Result<&'a str, LoadErrorKind> {
+    words.next().ok_or(LoadErrorKind::WrongArity {
+        element,
+        expected: 1,
+        found: 0,
+    })
+}
+
+/// Parse a single number, which must be strictly between the given limits
+fn parse_range<'a>(element: &'static str, words: impl Iterator<Item = &'a str>, min: f64, max: f64) -> Result<f64, LoadErrorKind> {
+    let value = parse_values(element, 1, words)?[0];
+    if value > min && value < max {
+        Ok(value)
+    } else {
+        Err(LoadErrorKind::Unexpected(format!("{} {}", element, value)))
+    }
+}
+
+fn to_vector(values: &[f64]) -> Vector3<f64> {
+    Vector3::new(values[0], values[1], values[2])
+}
+
+fn format_vector(vector: &Vector3<f64>) -> String {
+    // adding zero turns -0 into 0, which reads better
+    format!("{} {} {}", vector.x + 0.0, vector.y + 0.0, vector.z + 0.0)
+}
+
+/// The path relative to the directory if it's inside it, or else the absolute path
+fn relative_path(path: &Path, dir: &Path) -> PathBuf {
+    match path.strip_prefix(dir) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_same_nodes(saved: &[Node], loaded: &[Node]) {
+        assert_eq!(saved.len(), loaded.len());
+        for (saved, loaded) in saved.iter().zip(loaded) {
+            assert_eq!(saved.name, loaded.name);
+            assert_eq!(saved.model, loaded.model);
+            assert_eq!(saved.material, loaded.material);
+            assert_eq!(saved.spin, loaded.spin);
+            assert_eq!(saved.transform.translation, loaded.transform.translation);
+            assert_eq!(saved.transform.scale, loaded.transform.scale);
+            // rotations are saved as Euler angles, so they only come back to within rounding
+            assert!(saved.transform.rotation.angle_to(&loaded.transform.rotation) < 1e-9);
+            assert_same_nodes(&saved.children, &loaded.children);
+        }
+    }
+
+    #[test]
+    fn saved_scene_reads_back_the_same() {
+        let mut scene = Scene::read(Path::new(env!("CARGO_MANIFEST_DIR")).join("data/cessna.scene")).unwrap();
+        // move things away from the values in the file, so the test shows they're really saved
+        scene.nodes[0].transform.rotation = math::quaternion_from_euler(Vector3::new(10.0, -20.0, 30.0));
+        scene.nodes[0].transform.scale = 2.5;
+        scene.cameras[0].1.position = Point3::new(1.5, -2.0, 3.25);
+        scene.cameras[0].1.projection.fov = 60.0;
+        scene.models[0].object.set_group_visible("door", false);
+
+        let filename = std::env::temp_dir().join(format!("rather3d-test-{}.scene", std::process::id()));
+        scene.save(&filename).unwrap();
+        let loaded = Scene::read(&filename);
+        fs::remove_file(&filename).unwrap();
+        let loaded = loaded.unwrap();
+
+        let summary = |scene: &Scene| {
+            scene
+                .models
+                .iter()
+                .map(|model| (model.name.clone(), model.source.clone(), model.object.faces.len()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summary(&scene), summary(&loaded));
+        assert_eq!(scene.lights, loaded.lights);
+        assert_eq!(scene.cameras.len(), loaded.cameras.len());
+        for ((name, camera), (loaded_name, loaded_camera)) in scene.cameras.iter().zip(&loaded.cameras) {
+            assert_eq!(name, loaded_name);
+            assert_eq!(camera.position, loaded_camera.position);
+            assert_eq!(camera.projection, loaded_camera.projection);
+            assert!(camera.orientation.angle_to(&loaded_camera.orientation) < 1e-9);
+        }
+        assert_same_nodes(&scene.nodes, &loaded.nodes);
+
+        let hidden = |scene: &Scene| {
+            scene.models[0]
+                .object
+                .groups
+                .iter()
+                .filter(|group| !group.visible)
+                .map(|group| group.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(hidden(&scene), vec!["door".to_string()]);
+        assert_eq!(hidden(&loaded), hidden(&scene));
+    }
+
+    /// A directory with a small model in it, named with spaces, which is removed when dropped
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("rather3d-test-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("a  wing.obj"),
+                "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\ng left\nf 1 2 3\ng right\nf 1 3 4\n",
+            )
+            .unwrap();
+            Self(dir)
+        }
+
+        fn read(&self, text: &str) -> Result<Scene, LoadError> {
+            let filename = self.0.join("test.scene");
+            fs::write(&filename, text).unwrap();
+            Scene::read(filename)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn paths_can_have_spaces() {
+        let dir = TestDir::new("spaces");
+        let scene = dir.read("model wing a  wing.obj  \nsplit right_wing wing right\n").unwrap();
+        assert_eq!(scene.models[0].source, ModelSource::File(dir.0.join("a  wing.obj")));
+        assert_eq!(scene.models[1].object.faces.len(), 1);
+
+        let filename = dir.0.join("saved.scene");
+        scene.save(&filename).unwrap();
+        let loaded = Scene::read(&filename).unwrap();
+        assert_eq!(loaded.models[0].source, scene.models[0].source);
+        assert_eq!(loaded.models[1].source, scene.models[1].source);
+    }
+
+    #[test]
+    fn extra_words_are_errors() {
+        let dir = TestDir::new("extra");
+        let error = |text: &str| dir.read(text).map(|_| ()).unwrap_err().kind;
+        // the path is the rest of the line, so extra words make it a file that isn't there
+        assert!(matches!(
+            error("model wing a  wing.obj trailing junk"),
+            LoadErrorKind::Referenced(_)
+        ));
+        assert!(matches!(
+            error("model wing"),
+            LoadErrorKind::WrongArity {
+                element: "model",
+                expected: 2,
+                found: 1
+            }
+        ));
+        assert!(matches!(
+            error("model wing a  wing.obj\nsplit right_wing wing right junk"),
+            LoadErrorKind::UnknownName {
+                element: "group",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error("model wing a  wing.obj\nnode wing\n    mesh wing junk\nend"),
+            LoadErrorKind::WrongArity {
+                element: "mesh",
+                expected: 1,
+                found: 2
+            }
+        ));
+        assert!(matches!(
+            error("node wing\n    position 1 2 3 4\nend"),
+            LoadErrorKind::WrongArity {
+                element: "position",
+                expected: 3,
+                found: 4
+            }
+        ));
+        assert!(matches!(
+            error("camera start\n    fov 45 60\nend"),
+            LoadErrorKind::WrongArity {
+                element: "fov",
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,349 @@
+use std::fmt;
+use std::path::Path;
+
+use image::RgbaImage;
+use nalgebra::{Point2, Vector2};
+
+use crate::error::{LoadError, LoadErrorKind};
+
+/// How a texture is sampled between the centers of its texels.  Every filter reads from the mip
+/// level closest to the size the texture is drawn at, so distant textures don't shimmer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Use the texel the point falls in, which looks blocky up close
+    Nearest,
+    /// Blend the four texels around the point
+    Bilinear,
+    /// Blend bilinear samples from the two mip levels either side of the size the texture is
+    /// drawn at, so there's no visible seam where one level changes to the next
+    Trilinear,
+}
+
+impl Filter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "nearest" => Some(Filter::Nearest),
+            "bilinear" => Some(Filter::Bilinear),
+            "trilinear" => Some(Filter::Trilinear),
+            _ => None,
+        }
+    }
+}
+
+/// An image that's wrapped around a model using texture coordinates, along with its mip levels:
+/// smaller copies each half the size of the one before, down to a single texel
+#[derive(Clone)]
+pub struct Texture {
+    levels: Vec<Level>,
+}
+
+#[derive(Clone)]
+struct Level {
+    width: usize,
+    height: usize,
+    texels: Vec<[f32; 4]>,
+}
+
+impl Texture {
+    pub fn from_image(image: &RgbaImage) -> Self {
+        let mut levels = vec![Level {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            texels: image
+                .pixels()
+                .map(|pixel| pixel.0.map(|component| component as f32 / 255.0))
+                .collect(),
+        }];
+        while let Some(level) = levels.last().and_then(Level::shrink) {
+            levels.push(level);
+        }
+        Self {
+            levels,
+        }
+    }
+
+    pub fn read(filename: &Path) -> Result<Self, LoadError> {
+        let image = image::open(filename).map_err(|err| LoadError::new(filename, None, LoadErrorKind::Image(err)))?;
+        Ok(Self::from_image(&image.to_rgba8()))
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    /// The number of mip levels, including the full size image
+    pub fn level_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// The mip level to sample, given how far the texture coordinates move from one pixel to the
+    /// next along x and along y.  Level 0 is the full size image, and each whole step up halves it
+    pub fn level_of_detail(&self, derivatives: [Vector2<f64>; 2]) -> f64 {
+        let size = Vector2::new(self.width() as f64, self.height() as f64);
+        let texels_per_pixel = derivatives
+            .iter()
+            .map(|derivative| derivative.component_mul(&size).norm())
+            .fold(0.0, f64::max);
+        // max and min also turn NaN from degenerate derivatives into the full size image
+        texels_per_pixel.log2().max(0.0).min((self.levels.len() - 1) as f64)
+    }
+
+    /// The color at the texture coordinates, where (0, 0) is the bottom left corner of the image
+    /// and (1, 1) is the top right.  The derivatives are how far the texture coordinates move from
+    /// one pixel to the next along x and along y, which picks the mip level
+    pub fn sample(&self, texcoord: Point2<f64>, derivatives: [Vector2<f64>; 2], filter: Filter) -> [f32; 4] {
+        let lod = self.level_of_detail(derivatives);
+        let nearest_level = &self.levels[lod.round() as usize];
+        match filter {
+            Filter::Nearest => nearest_level.nearest(texcoord),
+            Filter::Bilinear => nearest_level.bilinear(texcoord),
+            Filter::Trilinear => {
+                let lower = lod.floor() as usize;
+                let upper = (lower + 1).min(self.levels.len() - 1);
+                blend(
+                    self.levels[lower].bilinear(texcoord),
+                    self.levels[upper].bilinear(texcoord),
+                    (lod - lower as f64) as f32,
+                )
+            },
+        }
+    }
+}
+
+impl Level {
+    /// The next mip level, with each texel the average of the two by two block of texels it covers,
+    /// or None once the level is a single texel.  A dimension that's odd folds its last row or
+    /// column into the last texel along it, which averages three rows or columns instead of two,
+    /// and one that's already 1 stays as it is
+    fn shrink(&self) -> Option<Self> {
+        if self.texels.is_empty() || (self.width <= 1 && self.height <= 1) {
+            return None;
+        }
+
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        // the texels along one dimension that go into the given texel of the next level
+        let covered = |i: usize, size: usize, next_size: usize| {
+            let end = if i + 1 == next_size { size } else { 2 * i + 2 };
+            2 * i..end
+        };
+        let mut texels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (xs, ys) = (covered(x, self.width, width), covered(y, self.height, height));
+                let weight = 1.0 / (xs.len() * ys.len()) as f32;
+                let mut sum = [0.0; 4];
+                for ty in ys {
+                    for tx in xs.clone() {
+                        let texel = self.texels[ty * self.width + tx];
+                        for (total, component) in sum.iter_mut().zip(texel) {
+                            *total += component * weight;
+                        }
+                    }
+                }
+                texels.push(sum);
+            }
+        }
+        Some(Self {
+            width,
+            height,
+            texels,
+        })
+    }
+
+    /// The texel at the given position, where positions outside the image wrap around so the
+    /// texture repeats
+    fn texel(&self, x: isize, y: isize) -> [f32; 4] {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    /// The position of the texture coordinates in texels, with y pointing down the image
+    fn position(&self, texcoord: Point2<f64>) -> (f64, f64) {
+        (texcoord.x * self.width as f64, (1.0 - texcoord.y) * self.height as f64)
+    }
+
+    fn nearest(&self, texcoord: Point2<f64>) -> [f32; 4] {
+        if self.texels.is_empty() {
+            return [1.0; 4];
+        }
+        let (x, y) = self.position(texcoord);
+        self.texel(x.floor() as isize, y.floor() as isize)
+    }
+
+    fn bilinear(&self, texcoord: Point2<f64>) -> [f32; 4] {
+        if self.texels.is_empty() {
+            return [1.0; 4];
+        }
+
+        // texel centers are half way across each texel
+        let (x, y) = self.position(texcoord);
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (left, top) = (x.floor(), y.floor());
+        let (tx, ty) = ((x - left) as f32, (y - top) as f32);
+        let (left, top) = (left as isize, top as isize);
+
+        let upper = blend(self.texel(left, top), self.texel(left + 1, top), tx);
+        let lower = blend(self.texel(left, top + 1), self.texel(left + 1, top + 1), tx);
+        blend(upper, lower, ty)
+    }
+}
+
+fn blend(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    [0, 1, 2, 3].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("width", &self.width())
+            .field("height", &self.height())
+            .field("levels", &self.levels.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+    /// Texture coordinates that don't change from pixel to pixel, so the full size image is used
+    const STILL: [Vector2<f64>; 2] = [Vector2::new(0.0, 0.0), Vector2::new(0.0, 0.0)];
+
+    /// A texture with the given rows of texels, top row first
+    fn texture(rows: &[&[[u8; 4]]]) -> Texture {
+        let image = RgbaImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            image::Rgba(rows[y as usize][x as usize])
+        });
+        Texture::from_image(&image)
+    }
+
+    fn assert_color(found: [f32; 4], expected: [f32; 4]) {
+        assert!(
+            found
+                .iter()
+                .zip(expected)
+                .all(|(found, expected)| (found - expected).abs() < 1e-6),
+            "expected {:?}, found {:?}",
+            expected,
+            found
+        );
+    }
+
+    fn color(texel: [u8; 4]) -> [f32; 4] {
+        texel.map(|component| component as f32 / 255.0)
+    }
+
+    #[test]
+    fn sampling_at_texel_centers_gives_the_texel() {
+        let texture = texture(&[&[RED, GREEN], &[BLUE, CLEAR]]);
+        for filter in [Filter::Nearest, Filter::Bilinear] {
+            let sample = |u, v| texture.sample(Point2::new(u, v), STILL, filter);
+            assert_color(sample(0.25, 0.75), color(RED));
+            assert_color(sample(0.75, 0.75), color(GREEN));
+            assert_color(sample(0.25, 0.25), color(BLUE));
+            assert_color(sample(0.75, 0.25), color(CLEAR));
+        }
+    }
+
+    #[test]
+    fn nearest_sampling_wraps_around() {
+        let texture = texture(&[&[RED, GREEN], &[BLUE, CLEAR]]);
+        let sample = |u, v| texture.sample(Point2::new(u, v), STILL, Filter::Nearest);
+        assert_color(sample(1.25, 0.75), color(RED));
+        assert_color(sample(-0.25, 0.75), color(GREEN));
+        assert_color(sample(0.25, -0.75), color(BLUE));
+        assert_color(sample(0.0, 1.0), color(RED));
+        assert_color(sample(1.0, 0.0), color(RED));
+    }
+
+    #[test]
+    fn bilinear_sampling_blends_across_the_wrap() {
+        let texture = texture(&[&[RED, GREEN], &[BLUE, CLEAR]]);
+        let sample = |u, v| texture.sample(Point2::new(u, v), STILL, Filter::Bilinear);
+        // half way between texel centers
+        assert_color(sample(0.5, 0.75), [0.5, 0.5, 0.0, 1.0]);
+        // the left and right edges blend the first and last columns, and the same for top and bottom
+        assert_color(sample(0.0, 0.75), [0.5, 0.5, 0.0, 1.0]);
+        assert_color(sample(1.0, 0.75), [0.5, 0.5, 0.0, 1.0]);
+        assert_color(sample(0.25, 1.0), [0.5, 0.0, 0.5, 1.0]);
+        assert_color(sample(0.0, 0.0), [0.25, 0.25, 0.25, 0.75]);
+    }
+
+    #[test]
+    fn mip_chain_halves_down_to_one_texel() {
+        let sizes = |texture: &Texture| {
+            texture
+                .levels
+                .iter()
+                .map(|level| (level.width, level.height))
+                .collect::<Vec<_>>()
+        };
+        let texture = Texture::from_image(&RgbaImage::new(8, 8));
+        assert_eq!(sizes(&texture), [(8, 8), (4, 4), (2, 2), (1, 1)]);
+        let texture = Texture::from_image(&RgbaImage::new(5, 3));
+        assert_eq!(sizes(&texture), [(5, 3), (2, 1), (1, 1)]);
+        let texture = Texture::from_image(&RgbaImage::new(553, 414));
+        assert_eq!(texture.level_count(), 10);
+        assert_eq!(sizes(&texture)[9], (1, 1));
+    }
+
+    #[test]
+    fn shrinking_averages_every_texel() {
+        let shrunk = texture(&[&[RED, GREEN], &[BLUE, CLEAR]]);
+        assert_color(shrunk.levels[1].texels[0], [0.25, 0.25, 0.25, 0.75]);
+
+        // odd sizes fold the last row or column into the last texel instead of losing it
+        let shrunk = texture(&[&[RED, GREEN, BLUE]]);
+        assert_color(shrunk.levels[1].texels[0], [1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0, 1.0]);
+        let shrunk = texture(&[&[RED, RED, GREEN, BLUE, CLEAR]]);
+        assert_color(shrunk.levels[1].texels[0], color(RED));
+        assert_color(shrunk.levels[1].texels[1], [0.0, 1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0]);
+        let shrunk = texture(&[&[RED, GREEN, BLUE], &[RED, GREEN, BLUE], &[CLEAR, CLEAR, CLEAR]]);
+        assert_color(shrunk.levels[1].texels[0], [2.0 / 9.0, 2.0 / 9.0, 2.0 / 9.0, 2.0 / 3.0]);
+    }
+
+    #[test]
+    fn level_of_detail_follows_the_texels_per_pixel() {
+        let texture = Texture::from_image(&RgbaImage::new(8, 8));
+        let lod = |x: f64, y: f64| texture.level_of_detail([Vector2::new(x, 0.0), Vector2::new(0.0, y)]);
+        assert_eq!(lod(1.0 / 8.0, 0.0), 0.0);
+        assert_eq!(lod(0.0, 0.5), 2.0);
+        // the larger of the two directions wins
+        assert_eq!(lod(0.25, 0.5), 2.0);
+        assert!((lod(2_f64.sqrt() / 8.0, 0.0) - 0.5).abs() < 1e-9);
+
+        // magnified and minified past the last level clamp to the ends of the chain
+        assert_eq!(lod(0.001, 0.0), 0.0);
+        assert_eq!(lod(0.0, 0.0), 0.0);
+        assert_eq!(lod(100.0, 0.0), 3.0);
+        assert_eq!(lod(f64::INFINITY, 0.0), 3.0);
+        assert_eq!(lod(f64::NAN, 0.0), 0.0);
+        assert_eq!(lod(f64::NAN, f64::NAN), 0.0);
+    }
+
+    #[test]
+    fn trilinear_sampling_blends_between_levels() {
+        let texture = texture(&[&[RED, GREEN], &[BLUE, CLEAR]]);
+        let average = [0.25, 0.25, 0.25, 0.75];
+        let center = Point2::new(0.25, 0.75);
+        let sample = |size: f64, filter| texture.sample(center, [Vector2::new(size / 2.0, 0.0), Vector2::zeros()], filter);
+
+        assert_color(sample(1.0, Filter::Trilinear), color(RED));
+        assert_color(sample(2.0, Filter::Trilinear), average);
+        assert_color(sample(2_f64.sqrt(), Filter::Trilinear), [0.625, 0.125, 0.125, 0.875]);
+        // the other filters pick the closest level
+        assert_color(sample(1.2, Filter::Nearest), color(RED));
+        assert_color(sample(1.2, Filter::Bilinear), color(RED));
+        assert_color(sample(1.8, Filter::Nearest), average);
+        assert_color(sample(1.8, Filter::Bilinear), average);
+    }
+}
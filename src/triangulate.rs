@@ -0,0 +1,215 @@
+use nalgebra::{Point2, Point3, Vector3};
+
+/// Calculate the normal of a polygon using Newell's method, which gives a best fit for non-planar
+/// polygons and points the way the right-hand rule would for counter-clockwise vertices.  The
+/// length of the result is twice the polygon's area, so it's zero for degenerate polygons
+pub fn polygon_normal(points: &[Point3<f64>]) -> Vector3<f64> {
+    let mut normal = Vector3::zeros();
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        normal.x += (a.y - b.y) * (a.z + b.z);
+        normal.y += (a.z - b.z) * (a.x + b.x);
+        normal.z += (a.x - b.x) * (a.y + b.y);
+    }
+    normal
+}
+
+/// Split a polygon into triangles using ear clipping, returning indices into the given points.
+///
+/// The polygon is projected onto the plane of its best-fit normal first, so concave and slightly
+/// non-planar polygons are handled.  If no ear can be found, because the polygon is degenerate or
+/// self-intersecting, whatever remains is split into a fan instead.  Polygons with fewer than 3
+/// points produce no triangles
+pub fn triangulate_polygon(points: &[Point3<f64>]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return vec![];
+    } else if points.len() == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    let normal = polygon_normal(points);
+    if normal.norm() == 0.0 {
+        return triangulate_fan(&(0..points.len()).collect::<Vec<usize>>());
+    }
+    let projected = project_to_plane(points, normal.normalize());
+    let epsilon = bounding_size(&projected).powi(2) * 1e-12;
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let corner = |i: usize| (remaining[(i + count - 1) % count], remaining[i], remaining[(i + 1) % count]);
+
+        if let Some(i) = (0..count).find(|&i| is_ear(&projected, &remaining, corner(i), epsilon)) {
+            let (prev, current, next) = corner(i);
+            triangles.push([prev, current, next]);
+            remaining.remove(i);
+        } else if let Some(i) = (0..count).find(|&i| cross(&projected, corner(i)).abs() <= epsilon) {
+            // a collinear vertex adds no area, so it can be dropped without emitting a triangle
+            remaining.remove(i);
+        } else {
+            triangles.extend(triangulate_fan(&remaining));
+            return triangles;
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+fn triangulate_fan(indices: &[usize]) -> Vec<[usize; 3]> {
+    (1..indices.len().saturating_sub(1))
+        .map(|i| [indices[0], indices[i], indices[i + 1]])
+        .collect()
+}
+
+/// Project the points onto the plane with the given normal, such that counter-clockwise polygons
+/// (with respect to the normal) remain counter-clockwise in 2D
+fn project_to_plane(points: &[Point3<f64>], normal: Vector3<f64>) -> Vec<Point2<f64>> {
+    let axis = if normal.x.abs() > 0.9 { Vector3::y() } else { Vector3::x() };
+    let u = axis.cross(&normal).normalize();
+    let v = normal.cross(&u);
+    points
+        .iter()
+        .map(|point| Point2::new(point.coords.dot(&u), point.coords.dot(&v)))
+        .collect()
+}
+
+fn bounding_size(points: &[Point2<f64>]) -> f64 {
+    let (mut min, mut max) = (points[0], points[0]);
+    for point in points {
+        min = min.inf(point);
+        max = max.sup(point);
+    }
+    (max - min).norm()
+}
+
+/// The z component of the cross product of the two edges meeting at the middle vertex, which is
+/// positive when the corner is convex
+fn cross(points: &[Point2<f64>], (a, b, c): (usize, usize, usize)) -> f64 {
+    let (ab, bc) = (points[b] - points[a], points[c] - points[b]);
+    ab.x * bc.y - ab.y * bc.x
+}
+
+fn is_ear(points: &[Point2<f64>], remaining: &[usize], corner: (usize, usize, usize), epsilon: f64) -> bool {
+    if cross(points, corner) <= epsilon {
+        return false;
+    }
+
+    let (a, b, c) = corner;
+    !remaining
+        .iter()
+        .filter(|&&i| i != a && i != b && i != c)
+        .any(|&i| is_inside_triangle(points[i], points[a], points[b], points[c]))
+}
+
+/// Whether the point is strictly inside the counter-clockwise triangle, or on one of its edges
+fn is_inside_triangle(point: Point2<f64>, a: Point2<f64>, b: Point2<f64>, c: Point2<f64>) -> bool {
+    let edge = |from: Point2<f64>, to: Point2<f64>| {
+        let (edge, offset) = (to - from, point - from);
+        edge.x * offset.y - edge.y * offset.x
+    };
+    // a vertex sharing a position with a corner (eg. where a polygon touches itself) doesn't block the ear
+    point != a && point != b && point != c && edge(a, b) >= 0.0 && edge(b, c) >= 0.0 && edge(c, a) >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The area of a polygon, from the length of its Newell normal
+    fn area(points: &[Point3<f64>]) -> f64 {
+        polygon_normal(points).norm() / 2.0
+    }
+
+    /// Check the triangles cover the polygon exactly, each winding the same way as the polygon
+    fn check_triangulation(points: &[Point3<f64>]) {
+        let triangles = triangulate_polygon(points);
+        assert_eq!(triangles.len(), points.len() - 2);
+
+        let normal = polygon_normal(points);
+        let mut total = 0.0;
+        for triangle in &triangles {
+            let corners = triangle.map(|i| points[i]);
+            assert!(
+                polygon_normal(&corners).dot(&normal) > 0.0,
+                "triangle {:?} is flipped",
+                triangle
+            );
+            total += area(&corners);
+        }
+        assert!(
+            (total - area(points)).abs() < 1e-9,
+            "triangles cover {} but the polygon is {}",
+            total,
+            area(points)
+        );
+    }
+
+    fn polygon(points: &[[f64; 2]]) -> Vec<Point3<f64>> {
+        points.iter().map(|[x, y]| Point3::new(*x, *y, 0.0)).collect()
+    }
+
+    #[test]
+    fn concave_l_shape() {
+        check_triangulation(&polygon(&[
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+        ]));
+    }
+
+    #[test]
+    fn concave_star() {
+        let points: Vec<[f64; 2]> = (0..10)
+            .map(|i| {
+                let radius = if i % 2 == 0 { 2.0 } else { 0.8 };
+                let angle = i as f64 * std::f64::consts::PI / 5.0;
+                [radius * angle.cos(), radius * angle.sin()]
+            })
+            .collect();
+        check_triangulation(&polygon(&points));
+    }
+
+    #[test]
+    fn clockwise_polygon() {
+        let mut points = polygon(&[[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [1.0, 1.0], [1.0, 2.0], [0.0, 2.0]]);
+        points.reverse();
+        assert!(polygon_normal(&points).z < 0.0);
+        check_triangulation(&points);
+    }
+
+    #[test]
+    fn polygon_in_another_plane() {
+        let points: Vec<Point3<f64>> = polygon(&[[0.0, 0.0], [2.0, 0.0], [2.0, 1.0], [1.0, 1.0], [1.0, 2.0], [0.0, 2.0]])
+            .iter()
+            .map(|point| Point3::new(point.y, 3.0, point.x))
+            .collect();
+        check_triangulation(&points);
+    }
+
+    #[test]
+    fn collinear_vertices_are_dropped() {
+        let points = polygon(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+        let triangles = triangulate_polygon(&points);
+        let total: f64 = triangles.iter().map(|triangle| area(&triangle.map(|i| points[i]))).sum();
+        assert!((total - 4.0).abs() < 1e-9);
+        assert!(triangles.len() <= points.len() - 2);
+    }
+
+    #[test]
+    fn degenerate_polygons() {
+        assert!(triangulate_polygon(&[]).is_empty());
+        assert!(triangulate_polygon(&polygon(&[[0.0, 0.0], [1.0, 0.0]])).is_empty());
+        assert_eq!(
+            triangulate_polygon(&polygon(&[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])),
+            vec![[0, 1, 2]]
+        );
+
+        // a polygon with no area still gives a triangle for each vertex past the first two
+        let line = polygon(&[[0.0, 0.0], [1.0, 0.0], [2.0, 0.0], [3.0, 0.0]]);
+        assert_eq!(triangulate_polygon(&line), vec![[0, 1, 2], [0, 2, 3]]);
+    }
+}